@@ -0,0 +1,754 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sui_sdk::types::base_types::SuiAddress;
+use thiserror::Error;
+use tokio::sync::{mpsc, watch::Receiver};
+use tracing::{error, info, instrument, warn};
+use x25519_dalek::StaticSecret;
+
+use crate::{
+    atoma::{AtomaSdk, AtomaSdkError},
+    audit::AuditLog,
+    config::{AnnouncerConfig, PostAuthoringConfig, SecretGuessingConfig},
+    engine::prompts::{self, SocialMediaPromptResponse},
+    moderation,
+    store::{self, StoreError},
+};
+
+/// The default file path of the local announcement log, used when no channel is configured
+const DEFAULT_ANNOUNCEMENTS_PATH: &str = "announcements.jsonl";
+
+/// The default maximum number of attempts to deliver an announcement to a channel
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// The default base delay in seconds between two attempts to deliver an announcement
+const DEFAULT_RETRY_BACKOFF_SECS: u64 = 30;
+
+/// The maximum delay in seconds between two attempts to deliver an announcement
+const MAX_RETRY_BACKOFF_SECS: u64 = 3600;
+
+/// The default minimum interval in seconds between two posts on X
+const DEFAULT_X_MIN_INTERVAL_SECS: u64 = 300;
+
+/// The default minimum interval in seconds between two Discord webhook messages
+const DEFAULT_DISCORD_MIN_INTERVAL_SECS: u64 = 2;
+
+/// The default minimum interval in seconds between two Telegram bot messages
+const DEFAULT_TELEGRAM_MIN_INTERVAL_SECS: u64 = 3;
+
+/// The maximum length of a post on X, in characters
+const X_MAX_LENGTH: usize = 280;
+
+/// The maximum length of a Discord message, in characters
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+/// The maximum length of a Telegram message, in characters
+const TELEGRAM_MAX_LENGTH: usize = 4096;
+
+/// The default persona of the model authoring the posts
+const DEFAULT_PERSONA: &str =
+    "the upbeat and concise game master of GuessAI, an on-chain secret guessing game";
+
+/// How often the dispatcher checks for announcements that are due for delivery
+const DISPATCH_TICK_MILLIS: u64 = 500;
+
+/// The X API endpoint to create a post
+const X_CREATE_POST_URL: &str = "https://api.twitter.com/2/tweets";
+
+/// The Telegram Bot API base URL
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+type Result<T> = std::result::Result<T, AnnouncerError>;
+
+/// A public announcement about the game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Announcement {
+    /// A player guessed the secret, and the treasury was paid out to them
+    Winner {
+        /// The winning player
+        sender: SuiAddress,
+
+        /// The winning guess
+        guess: String,
+
+        /// The treasury pool balance paid out to the winner
+        treasury_pool_balance: u64,
+
+        /// The digest of the payout transaction
+        tx_hash: String,
+    },
+
+    /// A new hint about the current secret
+    Hint {
+        /// The hint itself
+        hint: String,
+
+        /// How revealing the hint is
+        level: u32,
+
+        /// The guess count at which the hint was given
+        guess_count: u64,
+    },
+}
+
+impl Announcement {
+    /// Renders the announcement as a human readable message.
+    pub fn text(&self) -> String {
+        match self {
+            Announcement::Winner {
+                sender,
+                guess,
+                treasury_pool_balance,
+                tx_hash,
+            } => format!(
+                "We have a winner! {sender} guessed the secret \"{guess}\" and won the treasury pool of {treasury_pool_balance} MIST. Payout transaction: {tx_hash}"
+            ),
+            Announcement::Hint {
+                hint,
+                level,
+                guess_count,
+            } => format!("Hint #{level}, after {guess_count} guesses: {hint}"),
+        }
+    }
+}
+
+/// A channel on which announcements are published.
+#[async_trait]
+pub trait Announcer: Send + Sync {
+    /// The name of the channel, for logging.
+    fn name(&self) -> &str;
+
+    /// The minimum interval between two announcements on the channel.
+    fn min_interval(&self) -> Duration;
+
+    /// The maximum length of a message on the channel, in characters.
+    fn max_length(&self) -> usize;
+
+    /// Publishes an announcement on the channel, as the given message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnnouncerError` if the announcement could not be delivered. The
+    /// dispatcher retries failed deliveries, so implementations should not.
+    async fn announce(&self, announcement: &Announcement, text: &str) -> Result<()>;
+}
+
+/// Posts announcements on X (formerly Twitter), through the v2 API.
+pub struct XAnnouncer {
+    /// The HTTP client
+    client: reqwest::Client,
+
+    /// OAuth 2.0 user access token, with the `tweet.write` scope
+    access_token: String,
+
+    /// Minimum interval between two posts
+    min_interval: Duration,
+}
+
+impl XAnnouncer {
+    /// Constructor
+    pub fn new(access_token: String, min_interval: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            min_interval,
+        }
+    }
+}
+
+#[async_trait]
+impl Announcer for XAnnouncer {
+    fn name(&self) -> &str {
+        "x"
+    }
+
+    fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    fn max_length(&self) -> usize {
+        X_MAX_LENGTH
+    }
+
+    async fn announce(&self, _announcement: &Announcement, text: &str) -> Result<()> {
+        self.client
+            .post(X_CREATE_POST_URL)
+            .bearer_auth(&self.access_token)
+            .json(&json!({ "text": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Posts announcements on a Discord channel, through a webhook.
+pub struct DiscordAnnouncer {
+    /// The HTTP client
+    client: reqwest::Client,
+
+    /// The Discord webhook URL
+    webhook_url: String,
+
+    /// Minimum interval between two messages
+    min_interval: Duration,
+}
+
+impl DiscordAnnouncer {
+    /// Constructor
+    pub fn new(webhook_url: String, min_interval: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            webhook_url,
+            min_interval,
+        }
+    }
+}
+
+#[async_trait]
+impl Announcer for DiscordAnnouncer {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    fn max_length(&self) -> usize {
+        DISCORD_MAX_LENGTH
+    }
+
+    async fn announce(&self, _announcement: &Announcement, text: &str) -> Result<()> {
+        self.client
+            .post(&self.webhook_url)
+            .json(&json!({ "content": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Posts announcements on a Telegram chat, through a bot.
+pub struct TelegramAnnouncer {
+    /// The HTTP client
+    client: reqwest::Client,
+
+    /// The Telegram bot token
+    bot_token: String,
+
+    /// The identifier of the chat (or `@channelusername`) to post to
+    chat_id: String,
+
+    /// Minimum interval between two messages
+    min_interval: Duration,
+}
+
+impl TelegramAnnouncer {
+    /// Constructor
+    pub fn new(bot_token: String, chat_id: String, min_interval: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            bot_token,
+            chat_id,
+            min_interval,
+        }
+    }
+}
+
+#[async_trait]
+impl Announcer for TelegramAnnouncer {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    fn max_length(&self) -> usize {
+        TELEGRAM_MAX_LENGTH
+    }
+
+    async fn announce(&self, _announcement: &Announcement, text: &str) -> Result<()> {
+        self.client
+            .post(format!(
+                "{TELEGRAM_API_URL}/bot{}/sendMessage",
+                self.bot_token
+            ))
+            .json(&json!({ "chat_id": self.chat_id, "text": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// A record of the local announcement log.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnnouncementRecord {
+    /// The announcement
+    #[serde(flatten)]
+    pub announcement: Announcement,
+
+    /// The rendered message
+    pub text: String,
+
+    /// Unix timestamp (in seconds) at which the announcement was made
+    pub announced_at: u64,
+}
+
+/// Appends announcements to a local JSON lines file.
+pub struct JsonlAnnouncer {
+    /// The underlying append-only log
+    log: AuditLog,
+}
+
+impl JsonlAnnouncer {
+    /// Constructor
+    pub fn new(path: String) -> Self {
+        Self {
+            log: AuditLog::new(path),
+        }
+    }
+}
+
+#[async_trait]
+impl Announcer for JsonlAnnouncer {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    fn min_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn max_length(&self) -> usize {
+        usize::MAX
+    }
+
+    async fn announce(&self, announcement: &Announcement, text: &str) -> Result<()> {
+        self.log.append(&AnnouncementRecord {
+            announcement: announcement.clone(),
+            text: text.to_string(),
+            announced_at: store::unix_timestamp_secs(),
+        })?;
+        Ok(())
+    }
+}
+
+/// Builds the announcement channels from the application configuration.
+///
+/// If no channel is configured, announcements go to a local JSON lines file,
+/// so that they are never silently dropped.
+pub fn announcers_from_config(config: &AnnouncerConfig) -> Vec<Box<dyn Announcer>> {
+    let mut announcers: Vec<Box<dyn Announcer>> = vec![];
+    if let Some(x) = &config.x {
+        announcers.push(Box::new(XAnnouncer::new(
+            x.access_token.clone(),
+            Duration::from_secs(x.min_interval_secs.unwrap_or(DEFAULT_X_MIN_INTERVAL_SECS)),
+        )));
+    }
+    if let Some(discord) = &config.discord {
+        announcers.push(Box::new(DiscordAnnouncer::new(
+            discord.webhook_url.clone(),
+            Duration::from_secs(
+                discord
+                    .min_interval_secs
+                    .unwrap_or(DEFAULT_DISCORD_MIN_INTERVAL_SECS),
+            ),
+        )));
+    }
+    if let Some(telegram) = &config.telegram {
+        announcers.push(Box::new(TelegramAnnouncer::new(
+            telegram.bot_token.clone(),
+            telegram.chat_id.clone(),
+            Duration::from_secs(
+                telegram
+                    .min_interval_secs
+                    .unwrap_or(DEFAULT_TELEGRAM_MIN_INTERVAL_SECS),
+            ),
+        )));
+    }
+    if let Some(path) = &config.jsonl_path {
+        announcers.push(Box::new(JsonlAnnouncer::new(path.clone())));
+    }
+    if announcers.is_empty() {
+        announcers.push(Box::new(JsonlAnnouncer::new(
+            DEFAULT_ANNOUNCEMENTS_PATH.to_string(),
+        )));
+    }
+    announcers
+}
+
+/// Authors channel-appropriate posts from the facts of an announcement, with the model.
+///
+/// The model only ever sees the facts of the announcement, never the secret. Each post
+/// goes through moderation, and a post that fails it (or fails to be authored at all)
+/// is replaced by the announcement's plain template.
+pub struct PostAuthor {
+    /// The Atoma SDK instance
+    atoma_sdk: AtomaSdk,
+
+    /// The client private key, for the confidential requests
+    client_private_key: StaticSecret,
+
+    /// The model authoring the posts
+    model: String,
+
+    /// The persona the model writes as
+    persona: String,
+
+    /// Maximum length of a post, in characters, on top of the channel's own limit
+    max_length: Option<usize>,
+
+    /// Additional blocked terms for moderation
+    blocked_terms: Vec<String>,
+}
+
+impl PostAuthor {
+    /// Creates the post author from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig, authoring: &PostAuthoringConfig) -> Self {
+        let model = authoring
+            .model
+            .clone()
+            .unwrap_or_else(|| config.model.clone());
+        Self {
+            atoma_sdk: AtomaSdk::new(config.atoma_api_key.clone(), model.clone()),
+            client_private_key: StaticSecret::random_from_rng(rand::thread_rng()),
+            model,
+            persona: authoring
+                .persona
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PERSONA.to_string()),
+            max_length: authoring.max_length,
+            blocked_terms: authoring.blocked_terms.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Authors a post for an announcement, on the given channel.
+    ///
+    /// # Returns
+    ///
+    /// The post, or `None` if it could not be authored or was rejected by moderation.
+    #[instrument(level = "info", skip_all, fields(channel = channel))]
+    pub async fn author(
+        &self,
+        announcement: &Announcement,
+        channel: &str,
+        channel_max_length: usize,
+    ) -> Option<String> {
+        let max_length = self.max_length.map_or(channel_max_length, |max_length| {
+            max_length.min(channel_max_length)
+        });
+        let post = match self.request_post(announcement, channel, max_length).await {
+            Ok(post) => post,
+            Err(e) => {
+                warn!(
+                    target = "announcer",
+                    channel = channel,
+                    "Failed to author post: {e}"
+                );
+                return None;
+            }
+        };
+        if let Some(reason) =
+            moderation::moderate_post(&post, announcement, max_length, &self.blocked_terms)
+        {
+            warn!(
+                target = "announcer",
+                channel = channel,
+                "Authored post rejected by moderation: {reason}"
+            );
+            return None;
+        }
+        Some(post)
+    }
+
+    async fn request_post(
+        &self,
+        announcement: &Announcement,
+        channel: &str,
+        max_length: usize,
+    ) -> std::result::Result<String, AtomaSdkError> {
+        let facts = serde_json::to_string_pretty(announcement)?;
+        let prompt =
+            prompts::interact_with_social_media_prompt(&facts, channel, &self.persona, max_length);
+        let response_body = self
+            .atoma_sdk
+            .confidential_chat_completions(
+                &self.client_private_key,
+                serde_json::from_value(json!({
+                    "model": self.model.clone(),
+                    "messages": [
+                        { "role": "system", "content": prompt },
+                    ],
+                }))?,
+            )
+            .await?;
+        let SocialMediaPromptResponse { post } =
+            serde_json::from_str(&response_body.choices[0].message.content.clone())?;
+        Ok(post)
+    }
+}
+
+/// An announcement waiting to be delivered to a channel.
+struct PendingAnnouncement {
+    /// The announcement
+    announcement: Announcement,
+
+    /// The message to publish, authored the first time delivery is attempted
+    text: Option<String>,
+
+    /// The number of failed delivery attempts so far
+    attempts: u32,
+
+    /// The earliest time at which the next delivery attempt can be made
+    next_attempt_at: Instant,
+}
+
+/// An announcement channel, along with its retry queue and rate limiter state.
+struct Channel {
+    /// The channel
+    announcer: Box<dyn Announcer>,
+
+    /// Announcements waiting to be delivered, in order
+    queue: VecDeque<PendingAnnouncement>,
+
+    /// The time of the last delivery attempt, for rate limiting
+    last_attempt_at: Option<Instant>,
+}
+
+impl Channel {
+    /// Whether the rate limit of the channel allows a delivery attempt now.
+    fn is_ready(&self, now: Instant) -> bool {
+        self.last_attempt_at
+            .is_none_or(|at| now.duration_since(at) >= self.announcer.min_interval())
+    }
+}
+
+/// A handle to publish announcements, without waiting for their delivery.
+///
+/// Announcements are handed over to a background dispatcher, which delivers them to
+/// each channel while respecting its rate limit, and retries failed deliveries with
+/// an exponential backoff. Publishing never blocks, and never fails, so announcements
+/// cannot hold up payouts.
+#[derive(Clone)]
+pub struct AnnouncerHandle {
+    /// The sender side of the dispatcher's queue
+    sender: mpsc::UnboundedSender<Announcement>,
+}
+
+impl AnnouncerHandle {
+    /// Spawns the announcement dispatcher, and returns a handle to it.
+    ///
+    /// The dispatcher runs until the shutdown signal is received.
+    pub fn spawn(config: &SecretGuessingConfig, shutdown_signal: Receiver<bool>) -> Self {
+        let announcer_config = config.announcer.clone().unwrap_or_default();
+        let dispatcher = Dispatcher {
+            author: announcer_config
+                .authoring
+                .as_ref()
+                .map(|authoring| PostAuthor::from_config(config, authoring)),
+            channels: announcers_from_config(&announcer_config)
+                .into_iter()
+                .map(|announcer| Channel {
+                    announcer,
+                    queue: VecDeque::new(),
+                    last_attempt_at: None,
+                })
+                .collect(),
+            max_attempts: announcer_config
+                .max_attempts
+                .unwrap_or(DEFAULT_MAX_ATTEMPTS)
+                .max(1),
+            retry_backoff_secs: announcer_config
+                .retry_backoff_secs
+                .unwrap_or(DEFAULT_RETRY_BACKOFF_SECS),
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(dispatcher.run(receiver, shutdown_signal));
+        Self { sender }
+    }
+
+    /// Queues an announcement for delivery on every channel.
+    pub fn announce(&self, announcement: Announcement) {
+        if let Err(e) = self.sender.send(announcement) {
+            error!(
+                target = "announcer",
+                "Announcement dispatcher is not running, dropping announcement: {:?}", e.0
+            );
+        }
+    }
+}
+
+/// The background task delivering announcements to every channel.
+struct Dispatcher {
+    /// Authors the posts with the model, if enabled, otherwise the plain templates are used
+    author: Option<PostAuthor>,
+
+    /// The announcement channels
+    channels: Vec<Channel>,
+
+    /// Maximum number of attempts to deliver an announcement to a channel
+    max_attempts: u32,
+
+    /// Base delay in seconds between two attempts to deliver an announcement
+    retry_backoff_secs: u64,
+}
+
+impl Dispatcher {
+    #[instrument(level = "info", skip_all)]
+    async fn run(
+        mut self,
+        mut receiver: mpsc::UnboundedReceiver<Announcement>,
+        mut shutdown_signal: Receiver<bool>,
+    ) {
+        let mut tick = tokio::time::interval(Duration::from_millis(DISPATCH_TICK_MILLIS));
+        loop {
+            tokio::select! {
+                announcement = receiver.recv() => {
+                    let Some(announcement) = announcement else {
+                        break;
+                    };
+                    let now = Instant::now();
+                    for channel in &mut self.channels {
+                        channel.queue.push_back(PendingAnnouncement {
+                            announcement: announcement.clone(),
+                            text: None,
+                            attempts: 0,
+                            next_attempt_at: now,
+                        });
+                    }
+                    self.deliver_due().await;
+                }
+                _ = tick.tick() => {
+                    self.deliver_due().await;
+                }
+                shutdown_signal_changed = shutdown_signal.changed() => {
+                    match shutdown_signal_changed {
+                        Ok(()) => {
+                            if *shutdown_signal.borrow() {
+                                info!(
+                                    target = "announcer",
+                                    "Shutdown signal received, gracefully stopping announcement dispatcher..."
+                                );
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            error!(
+                                target = "announcer",
+                                "Shutdown signal channel closed: {e}"
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        for channel in &self.channels {
+            if !channel.queue.is_empty() {
+                warn!(
+                    target = "announcer",
+                    channel = channel.announcer.name(),
+                    "Dropping {} undelivered announcements",
+                    channel.queue.len()
+                );
+            }
+        }
+    }
+
+    /// Attempts to deliver, on each channel, the oldest announcement that is due,
+    /// if the channel's rate limit allows it.
+    async fn deliver_due(&mut self) {
+        let max_attempts = self.max_attempts;
+        let retry_backoff_secs = self.retry_backoff_secs;
+        let author = self.author.as_ref();
+        for channel in &mut self.channels {
+            let now = Instant::now();
+            if !channel.is_ready(now) {
+                continue;
+            }
+            let Some(pending) = channel.queue.front_mut() else {
+                continue;
+            };
+            if pending.next_attempt_at > now {
+                continue;
+            }
+
+            if pending.text.is_none() {
+                let max_length = channel.announcer.max_length();
+                let post = match author {
+                    Some(author) => {
+                        author
+                            .author(&pending.announcement, channel.announcer.name(), max_length)
+                            .await
+                    }
+                    None => None,
+                };
+                pending.text = Some(post.unwrap_or_else(|| {
+                    moderation::truncate_post(&pending.announcement.text(), max_length)
+                }));
+            }
+            let text = pending.text.clone().unwrap_or_default();
+
+            channel.last_attempt_at = Some(Instant::now());
+            match channel
+                .announcer
+                .announce(&pending.announcement, &text)
+                .await
+            {
+                Ok(()) => {
+                    info!(
+                        target = "announcer",
+                        channel = channel.announcer.name(),
+                        "Announcement delivered"
+                    );
+                    channel.queue.pop_front();
+                }
+                Err(e) => {
+                    pending.attempts += 1;
+                    if pending.attempts >= max_attempts {
+                        error!(
+                            target = "announcer",
+                            channel = channel.announcer.name(),
+                            attempts = pending.attempts,
+                            "Giving up on announcement {:?}: {e}",
+                            pending.announcement
+                        );
+                        channel.queue.pop_front();
+                    } else {
+                        let exponent = (pending.attempts - 1).min(16);
+                        let backoff_secs = retry_backoff_secs
+                            .saturating_mul(1u64 << exponent)
+                            .min(MAX_RETRY_BACKOFF_SECS);
+                        pending.next_attempt_at = now + Duration::from_secs(backoff_secs);
+                        warn!(
+                            target = "announcer",
+                            channel = channel.announcer.name(),
+                            attempts = pending.attempts,
+                            "Failed to deliver announcement, retrying in {backoff_secs}s: {e}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AnnouncerError {
+    #[error("Failed to send announcement: {0}")]
+    RequestError(#[from] reqwest::Error),
+    #[error("Failed to write announcement: {0}")]
+    StoreError(#[from] StoreError),
+}
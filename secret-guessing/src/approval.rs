@@ -0,0 +1,870 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::{base_types::SuiAddress, event::EventID};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::watch::Receiver,
+};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    config::{ApprovalTimeoutAction, SecretGuessingConfig},
+    dead_letter::entry_id,
+    engine::events::SubmittedGuess,
+    payout::PayoutPlan,
+    quorum::JudgeVote,
+    store::{self, JsonStore, StoreError},
+};
+
+/// The default file path for the payout approval queue
+const DEFAULT_APPROVAL_QUEUE_PATH: &str = "payout_approvals.json";
+
+/// The largest HTTP request head read by the approval endpoint, in bytes
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Seconds within which a connection to the approval endpoint must send its request
+const REQUEST_READ_TIMEOUT_SECS: u64 = 10;
+
+type Result<T> = std::result::Result<T, ApprovalError>;
+
+/// The operator's decision on a queued payout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    /// The payout waits for an operator
+    Pending,
+
+    /// The payout may be sent
+    Approved,
+
+    /// The payout must not be sent, the guess is treated as an incorrect one
+    Rejected,
+
+    /// The payout is not sent, as an earlier guess of the same round won it
+    Superseded,
+}
+
+/// What the engine does with a payout of the approval queue, once it may act on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Settlement {
+    /// Send the approved payout, which wins its round
+    Pay,
+
+    /// Treat the rejected guess as an incorrect one
+    Reject,
+
+    /// Drop the payout, as an earlier guess of its round won it
+    Supersede,
+}
+
+/// The payout of a correct guess waiting for an operator's approval, along with
+/// everything the operator needs to check the verdict.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApprovalEntry {
+    /// The identifier of the entry, in the form `<tx_digest>:<event_seq>`
+    pub id: String,
+
+    /// The on-chain identifier of the winning guess event
+    pub event_id: EventID,
+
+    /// The player who submitted the guess
+    pub sender: SuiAddress,
+
+    /// The guess itself
+    pub guess: String,
+
+    /// The guess count at the time of the guess
+    pub guess_count: u64,
+
+    /// The treasury pool balance reported by the guess event, in MIST
+    pub treasury_pool_balance: u64,
+
+    /// The other correct guesses of the same checkpoint sharing the payout, with their sender
+    pub co_winners: Vec<(SuiAddress, String)>,
+
+    /// The amounts paid out of the treasury pool, computed when the payout was queued.
+    /// Exactly these amounts are sent once approved, whatever the pool holds by then
+    pub plan: PayoutPlan,
+
+    /// The round of the guess. If the round rotated before the payout was approved, it is
+    /// still sent, but the current round is left open
+    pub round: u64,
+
+    /// The layer of the judging pipeline that deemed the guess correct
+    pub judged_by: String,
+
+    /// The explanation of the verdict
+    pub explanation: String,
+
+    /// The votes of the judges, with the signatures of their responses. Empty if the
+    /// guess was settled without the model
+    pub votes: Vec<JudgeVote>,
+
+    /// The operator's decision
+    pub status: ApprovalStatus,
+
+    /// Unix timestamp (in seconds) at which the payout was queued
+    pub queued_at: u64,
+
+    /// Unix timestamp (in seconds) at which the payout was approved or rejected
+    pub decided_at: Option<u64>,
+
+    /// Who decided on the payout, e.g. `cli`, `http` or `timeout`
+    pub decided_by: Option<String>,
+
+    /// Unix timestamp (in seconds) at which the engine acted on the decision
+    pub settled_at: Option<u64>,
+}
+
+/// A persistent queue of high-value payouts, waiting for an operator's approval.
+///
+/// Operators approve or reject the queued payouts through the CLI or the local HTTP
+/// endpoint, and the engine picks up the decisions, sending the approved payouts.
+/// Entries are kept once settled, so that a replayed guess is not queued again. Each
+/// update holds the queue's file lock, as the CLI updates it from another process, so
+/// async callers go through [`ApprovalQueue::blocking`].
+#[derive(Clone)]
+pub struct ApprovalQueue {
+    /// The payouts, keyed by their identifier
+    entries: JsonStore<ApprovalEntry>,
+}
+
+impl ApprovalQueue {
+    /// Constructor
+    pub fn new(path: String) -> Self {
+        Self {
+            entries: JsonStore::new(path),
+        }
+    }
+
+    /// Creates the approval queue from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        Self::new(
+            config
+                .payout_approval
+                .as_ref()
+                .and_then(|approval| approval.queue_path.clone())
+                .unwrap_or_else(|| DEFAULT_APPROVAL_QUEUE_PATH.to_string()),
+        )
+    }
+
+    /// Returns all the payouts, ordered by their identifier.
+    pub fn list(&self) -> Result<Vec<ApprovalEntry>> {
+        Ok(self.entries.values()?)
+    }
+
+    /// Returns the payouts waiting for an operator.
+    pub fn pending(&self) -> Result<Vec<ApprovalEntry>> {
+        Ok(self
+            .entries
+            .values()?
+            .into_iter()
+            .filter(|entry| entry.status == ApprovalStatus::Pending)
+            .collect())
+    }
+
+    /// Whether a payout of the given round was queued and not settled yet, in which case
+    /// a later correct guess of the round queues behind it.
+    pub fn has_unsettled(&self, round: u64) -> Result<bool> {
+        Ok(self
+            .entries
+            .read()?
+            .values()
+            .any(|entry| entry.round == round && entry.settled_at.is_none()))
+    }
+
+    /// Runs an operation on the queue on the blocking thread pool, as it may wait for the
+    /// queue's file lock and does synchronous file I/O.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the operation, or `ApprovalError::JoinError` if it panicked.
+    pub async fn blocking<T, F>(&self, operation: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ApprovalQueue) -> Result<T> + Send + 'static,
+    {
+        let queue = self.clone();
+        tokio::task::spawn_blocking(move || operation(&queue)).await?
+    }
+
+    /// Queues the payout of a correct guess for approval.
+    ///
+    /// If the guess was already queued (e.g. it is replayed after a restart), the
+    /// existing entry is returned unchanged, along with `false`.
+    ///
+    /// # Returns
+    ///
+    /// The entry, and whether it was newly queued.
+    #[instrument(level = "info", skip_all, fields(guess = %entry.guess))]
+    pub fn enqueue(&self, entry: ApprovalEntry) -> Result<(ApprovalEntry, bool)> {
+        let existing = self.entries.update(|entries| {
+            let existing = entries.get(&entry.id).cloned();
+            if existing.is_none() {
+                entries.insert(entry.id.clone(), entry.clone());
+            }
+            Ok::<_, ApprovalError>(existing)
+        })?;
+        if let Some(existing) = existing {
+            return Ok((existing, false));
+        }
+        info!(
+            target = "approval-queue",
+            id = %entry.id,
+            treasury_pool_balance = entry.treasury_pool_balance,
+            "Payout queued for operator approval"
+        );
+        Ok((entry, true))
+    }
+
+    /// Approves a pending payout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ApprovalError::UnknownPayout` if no payout is queued with this identifier,
+    /// and `ApprovalError::AlreadyDecided` if it was already approved or rejected.
+    pub fn approve(&self, id: &str, decided_by: &str) -> Result<ApprovalEntry> {
+        self.decide(id, ApprovalStatus::Approved, decided_by)
+    }
+
+    /// Rejects a pending payout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ApprovalError::UnknownPayout` if no payout is queued with this identifier,
+    /// and `ApprovalError::AlreadyDecided` if it was already approved or rejected.
+    pub fn reject(&self, id: &str, decided_by: &str) -> Result<ApprovalEntry> {
+        self.decide(id, ApprovalStatus::Rejected, decided_by)
+    }
+
+    /// Decides the pending payouts queued for longer than the timeout.
+    ///
+    /// # Returns
+    ///
+    /// The payouts that timed out.
+    pub fn expire(
+        &self,
+        now: u64,
+        timeout_secs: u64,
+        action: ApprovalTimeoutAction,
+    ) -> Result<Vec<ApprovalEntry>> {
+        let status = match action {
+            ApprovalTimeoutAction::Approve => ApprovalStatus::Approved,
+            ApprovalTimeoutAction::Reject => ApprovalStatus::Rejected,
+        };
+        let is_expired = |entry: &ApprovalEntry| {
+            entry.status == ApprovalStatus::Pending
+                && entry.queued_at.saturating_add(timeout_secs) <= now
+        };
+        // Checked first without the lock, so that the queue is not rewritten on every check
+        if !self.entries.read()?.values().any(is_expired) {
+            return Ok(vec![]);
+        }
+        self.entries.update(|entries| {
+            let mut expired = vec![];
+            for entry in entries.values_mut().filter(|entry| is_expired(entry)) {
+                entry.status = status;
+                entry.decided_at = Some(now);
+                entry.decided_by = Some("timeout".to_string());
+                expired.push(entry.clone());
+            }
+            Ok(expired)
+        })
+    }
+
+    /// Records that the engine acted on the decision on a payout.
+    pub fn settle(&self, id: &str) -> Result<()> {
+        self.entries.update(|entries| {
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| ApprovalError::UnknownPayout(id.to_string()))?;
+            entry.settled_at = Some(store::unix_timestamp_secs());
+            Ok(())
+        })
+    }
+
+    /// Settles a payout without sending it, as an earlier guess of its round won it.
+    pub fn supersede(&self, id: &str) -> Result<()> {
+        let now = store::unix_timestamp_secs();
+        self.entries.update(|entries| {
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| ApprovalError::UnknownPayout(id.to_string()))?;
+            entry.status = ApprovalStatus::Superseded;
+            entry.decided_at.get_or_insert(now);
+            entry.decided_by.get_or_insert_with(|| "engine".to_string());
+            entry.settled_at = Some(now);
+            Ok(())
+        })
+    }
+
+    #[instrument(level = "info", skip(self), fields(status = ?status))]
+    fn decide(&self, id: &str, status: ApprovalStatus, decided_by: &str) -> Result<ApprovalEntry> {
+        let entry = self.entries.update(|entries| {
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| ApprovalError::UnknownPayout(id.to_string()))?;
+            if entry.status != ApprovalStatus::Pending {
+                return Err(ApprovalError::AlreadyDecided(id.to_string()));
+            }
+            entry.status = status;
+            entry.decided_at = Some(store::unix_timestamp_secs());
+            entry.decided_by = Some(decided_by.to_string());
+            Ok(entry.clone())
+        })?;
+        info!(
+            target = "approval-queue",
+            id = %id,
+            decided_by = %decided_by,
+            "Payout {status:?} by operator"
+        );
+        Ok(entry)
+    }
+}
+
+impl ApprovalEntry {
+    /// Creates a pending entry for the payout of a guess.
+    pub fn new(
+        submitted: &SubmittedGuess,
+        co_winners: Vec<(SuiAddress, String)>,
+        plan: PayoutPlan,
+        round: u64,
+        judged_by: String,
+        explanation: String,
+        votes: Vec<JudgeVote>,
+    ) -> Self {
+        Self {
+            id: entry_id(&submitted.event_id),
+            event_id: submitted.event_id,
+            sender: submitted.sender,
+            guess: submitted.guess.clone(),
+            guess_count: submitted.guess_count,
+            treasury_pool_balance: submitted.treasury_pool_balance,
+            co_winners,
+            plan,
+            round,
+            judged_by,
+            explanation,
+            votes,
+            status: ApprovalStatus::Pending,
+            queued_at: store::unix_timestamp_secs(),
+            decided_at: None,
+            decided_by: None,
+            settled_at: None,
+        }
+    }
+
+    /// The guess whose payout is queued.
+    pub fn submitted(&self) -> SubmittedGuess {
+        SubmittedGuess {
+            event_id: self.event_id,
+            sender: self.sender,
+            guess: self.guess.clone(),
+            guess_count: self.guess_count,
+            treasury_pool_balance: self.treasury_pool_balance,
+        }
+    }
+}
+
+impl std::fmt::Display for ApprovalEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} status={:?} sender={} guess={:?} treasury_pool_balance={} round={} judged_by={} queued_at={}",
+            self.id,
+            self.status,
+            self.sender,
+            self.guess,
+            self.treasury_pool_balance,
+            self.round,
+            self.judged_by,
+            self.queued_at
+        )?;
+        write!(f, "\n    explanation={}", self.explanation)?;
+        for (co_winner, guess) in &self.co_winners {
+            write!(f, "\n    co_winner={co_winner} guess={guess:?}")?;
+        }
+        for vote in &self.votes {
+            write!(
+                f,
+                "\n    {vote}\n        signature={} response_hash={}",
+                vote.signature.as_deref().unwrap_or("-"),
+                vote.response_hash.as_deref().unwrap_or("-")
+            )?;
+        }
+        if let (Some(decided_by), Some(decided_at)) = (&self.decided_by, self.decided_at) {
+            write!(f, "\n    decided_by={decided_by} decided_at={decided_at}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decides what the engine does with the unsettled payouts of the queue, going through
+/// each round's payouts in the order their guesses were made.
+///
+/// A rejected payout is settled as soon as it is decided. The first approved payout of a
+/// round wins it, but only once every earlier payout of the round is decided, so that a
+/// pending payout holds back the later ones. Once a round is won, its remaining payouts
+/// are superseded, whether they were decided or not.
+///
+/// # Returns
+///
+/// The payouts the engine may act on, each with what it must do.
+pub fn settlements(mut entries: Vec<ApprovalEntry>) -> Vec<(ApprovalEntry, Settlement)> {
+    entries.sort_by_key(|entry| (entry.round, entry.guess_count, entry.queued_at));
+    let mut settlements = vec![];
+    let mut round = None;
+    let (mut won, mut waiting) = (false, false);
+    for entry in entries {
+        if round != Some(entry.round) {
+            round = Some(entry.round);
+            (won, waiting) = (false, false);
+        }
+        if entry.settled_at.is_some() {
+            won |= entry.status == ApprovalStatus::Approved;
+            continue;
+        }
+        let settlement = match entry.status {
+            ApprovalStatus::Rejected => Settlement::Reject,
+            _ if won => Settlement::Supersede,
+            ApprovalStatus::Approved if !waiting => {
+                won = true;
+                Settlement::Pay
+            }
+            ApprovalStatus::Pending => {
+                waiting = true;
+                continue;
+            }
+            ApprovalStatus::Approved | ApprovalStatus::Superseded => continue,
+        };
+        settlements.push((entry, settlement));
+    }
+    settlements
+}
+
+/// Serves the local HTTP endpoint through which operators approve or reject payouts,
+/// until the shutdown signal is received.
+///
+/// Requests must carry the configured token in a `Authorization: Bearer <token>` header,
+/// and a `Host` (and `Origin`, if any) naming the endpoint's own address, so that a web
+/// page opened by the operator cannot reach the endpoint, e.g. through DNS rebinding.
+/// Each connection is handled in its own task, and dropped if its request is not
+/// received within [`REQUEST_READ_TIMEOUT_SECS`]. The endpoint answers:
+///
+/// * `GET /approvals` - The queued payouts
+/// * `POST /approvals/<id>/approve` - Approves a pending payout
+/// * `POST /approvals/<id>/reject` - Rejects a pending payout
+///
+/// # Errors
+///
+/// Returns `ApprovalError::MissingToken` if no token is configured, and
+/// `ApprovalError::IoError` if the address cannot be bound.
+#[instrument(level = "info", skip_all, fields(addr = %addr))]
+pub async fn serve(
+    addr: String,
+    token: Option<String>,
+    queue: ApprovalQueue,
+    mut shutdown_signal: Receiver<bool>,
+) -> Result<()> {
+    let token = token
+        .filter(|token| !token.is_empty())
+        .ok_or(ApprovalError::MissingToken)?;
+    let listener = TcpListener::bind(&addr).await?;
+    let local_addr = listener.local_addr()?;
+    if !local_addr.ip().is_loopback() {
+        warn!(
+            target = "approval-endpoint",
+            "The payout approval endpoint is bound to a non-loopback address"
+        );
+    }
+    let endpoint = Arc::new(Endpoint {
+        queue,
+        token,
+        hosts: vec![
+            addr.to_ascii_lowercase(),
+            local_addr.to_string(),
+            format!("localhost:{}", local_addr.port()),
+        ],
+    });
+    info!(
+        target = "approval-endpoint",
+        "Serving the payout approval endpoint on: {addr}"
+    );
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                match accepted {
+                    Ok((stream, _)) => {
+                        let endpoint = endpoint.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = endpoint.handle_connection(stream).await {
+                                warn!(
+                                    target = "approval-endpoint",
+                                    "Failed to handle approval request: {e}"
+                                );
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        error!(
+                            target = "approval-endpoint",
+                            "Failed to accept connection: {e}"
+                        );
+                    }
+                }
+            }
+            changed = shutdown_signal.changed() => {
+                if changed.is_err() || *shutdown_signal.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The state shared by the connections to the approval endpoint.
+struct Endpoint {
+    /// The approval queue
+    queue: ApprovalQueue,
+
+    /// The bearer token requests must carry
+    token: String,
+
+    /// The `Host` values naming the endpoint, lowercase
+    hosts: Vec<String>,
+}
+
+/// The head of an HTTP request, as read from a connection.
+enum RequestHead {
+    /// The request line and headers
+    Complete(String),
+
+    /// The head exceeds [`MAX_REQUEST_SIZE`]
+    TooLarge,
+
+    /// The connection was closed before the end of the head
+    Incomplete,
+}
+
+impl Endpoint {
+    /// Reads a single HTTP request from a connection, and writes its response.
+    async fn handle_connection(&self, mut stream: TcpStream) -> Result<()> {
+        let head = match tokio::time::timeout(
+            Duration::from_secs(REQUEST_READ_TIMEOUT_SECS),
+            read_request_head(&mut stream),
+        )
+        .await
+        {
+            Ok(head) => head?,
+            Err(_) => {
+                return write_error(&mut stream, "408 Request Timeout", "request timeout").await
+            }
+        };
+        let head = match head {
+            RequestHead::Complete(head) => head,
+            RequestHead::TooLarge => {
+                return write_error(&mut stream, "413 Payload Too Large", "request too large").await
+            }
+            RequestHead::Incomplete => {
+                return write_error(&mut stream, "400 Bad Request", "incomplete request").await
+            }
+        };
+
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default();
+        let path = request_line.next().unwrap_or_default();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+            .collect::<Vec<_>>();
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header == name)
+                .map(|(_, value)| *value)
+        };
+
+        if !self.is_own_origin(header("host"), header("origin")) {
+            return write_error(&mut stream, "403 Forbidden", "forbidden").await;
+        }
+        if !is_authorized(header("authorization"), &self.token) {
+            return write_error(&mut stream, "401 Unauthorized", "unauthorized").await;
+        }
+
+        let segments = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let result = match (method, segments.as_slice()) {
+            ("GET", ["approvals"]) => self
+                .queue
+                .blocking(ApprovalQueue::list)
+                .await
+                .map(serde_json::to_value),
+            ("POST", ["approvals", id, "approve"]) => {
+                let id = id.to_string();
+                self.queue
+                    .blocking(move |queue| queue.approve(&id, "http"))
+                    .await
+                    .map(serde_json::to_value)
+            }
+            ("POST", ["approvals", id, "reject"]) => {
+                let id = id.to_string();
+                self.queue
+                    .blocking(move |queue| queue.reject(&id, "http"))
+                    .await
+                    .map(serde_json::to_value)
+            }
+            _ => return write_error(&mut stream, "404 Not Found", "not found").await,
+        };
+        match result {
+            Ok(Ok(body)) => write_response(&mut stream, "200 OK", &body.to_string()).await,
+            Ok(Err(e)) => Err(e.into()),
+            Err(e) => {
+                let status = match e {
+                    ApprovalError::UnknownPayout(_) => "404 Not Found",
+                    ApprovalError::AlreadyDecided(_) => "409 Conflict",
+                    _ => "500 Internal Server Error",
+                };
+                write_error(&mut stream, status, &e.to_string()).await
+            }
+        }
+    }
+
+    /// Whether a request is addressed to the endpoint itself, and not sent by a web
+    /// page of another origin.
+    fn is_own_origin(&self, host: Option<&str>, origin: Option<&str>) -> bool {
+        let is_own_host = |host: &str| self.hosts.contains(&host.to_ascii_lowercase());
+        let Some(host) = host else {
+            return false;
+        };
+        match origin {
+            Some(origin) => {
+                is_own_host(host) && origin.strip_prefix("http://").is_some_and(is_own_host)
+            }
+            None => is_own_host(host),
+        }
+    }
+}
+
+/// Reads the head of an HTTP request, up to [`MAX_REQUEST_SIZE`] bytes. The body, if
+/// any, is not read, as no request of the endpoint has one.
+async fn read_request_head(stream: &mut TcpStream) -> Result<RequestHead> {
+    let mut request = Vec::with_capacity(1024);
+    let mut buffer = [0u8; 1024];
+    loop {
+        if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            if end > MAX_REQUEST_SIZE {
+                return Ok(RequestHead::TooLarge);
+            }
+            return Ok(RequestHead::Complete(
+                String::from_utf8_lossy(&request[..end]).into_owned(),
+            ));
+        }
+        if request.len() > MAX_REQUEST_SIZE {
+            return Ok(RequestHead::TooLarge);
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(RequestHead::Incomplete);
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+}
+
+/// Whether the `Authorization` header of a request carries the endpoint's bearer token.
+///
+/// The token is compared in constant time, so that it cannot be guessed byte by byte.
+fn is_authorized(authorization: Option<&str>, token: &str) -> bool {
+    let Some(presented) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn write_error(stream: &mut TcpStream, status: &str, error: &str) -> Result<()> {
+    let body = serde_json::json!({ "error": error }).to_string();
+    write_response(stream, status, &body).await
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(stream.shutdown().await?)
+}
+
+#[derive(Debug, Error)]
+pub enum ApprovalError {
+    #[error("Approval queue store error: {0}")]
+    StoreError(#[from] StoreError),
+    #[error("No payout queued for approval with id `{0}`")]
+    UnknownPayout(String),
+    #[error("Payout `{0}` was already decided")]
+    AlreadyDecided(String),
+    #[error("Approval endpoint I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to serialize approval response: {0}")]
+    SerializeError(#[from] serde_json::Error),
+    #[error("The approval endpoint requires a bearer token (`payout_approval.http_token`)")]
+    MissingToken,
+    #[error("Approval queue operation panicked: {0}")]
+    JoinError(#[from] tokio::task::JoinError),
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_sdk::types::digests::TransactionDigest;
+
+    use super::*;
+
+    fn entry(round: u64, guess_count: u64, status: ApprovalStatus) -> ApprovalEntry {
+        let submitted = SubmittedGuess {
+            event_id: EventID {
+                tx_digest: TransactionDigest::random(),
+                event_seq: 0,
+            },
+            sender: SuiAddress::random_for_testing_only(),
+            guess: format!("guess {guess_count}"),
+            guess_count,
+            treasury_pool_balance: 1_000_000,
+        };
+        let mut entry = ApprovalEntry::new(
+            &submitted,
+            vec![],
+            PayoutPlan {
+                recipients: vec![],
+                house_fee: 0,
+                rollover: 0,
+            },
+            round,
+            "model".to_string(),
+            "The guess is the secret".to_string(),
+            vec![],
+        );
+        entry.status = status;
+        entry
+    }
+
+    fn settle(entry: &mut ApprovalEntry) {
+        entry.settled_at = Some(store::unix_timestamp_secs());
+    }
+
+    fn guesses(settlements: &[(ApprovalEntry, Settlement)]) -> Vec<(u64, Settlement)> {
+        settlements
+            .iter()
+            .map(|(entry, settlement)| (entry.guess_count, *settlement))
+            .collect()
+    }
+
+    #[test]
+    fn later_payouts_wait_for_an_earlier_pending_one() {
+        let entries = vec![
+            entry(1, 10, ApprovalStatus::Pending),
+            entry(1, 12, ApprovalStatus::Approved),
+            entry(1, 15, ApprovalStatus::Pending),
+        ];
+        assert!(settlements(entries).is_empty());
+    }
+
+    #[test]
+    fn rejection_after_later_guesses_pays_the_next_approved_one() {
+        // The first correct guess is rejected once two later ones were queued behind it:
+        // the next approved one wins the round, and the last one is superseded
+        let entries = vec![
+            entry(1, 15, ApprovalStatus::Pending),
+            entry(1, 10, ApprovalStatus::Rejected),
+            entry(1, 12, ApprovalStatus::Approved),
+        ];
+        assert_eq!(
+            guesses(&settlements(entries)),
+            vec![
+                (10, Settlement::Reject),
+                (12, Settlement::Pay),
+                (15, Settlement::Supersede),
+            ]
+        );
+    }
+
+    #[test]
+    fn approval_of_the_first_payout_supersedes_the_later_ones() {
+        let entries = vec![
+            entry(1, 10, ApprovalStatus::Approved),
+            entry(1, 12, ApprovalStatus::Approved),
+            entry(1, 15, ApprovalStatus::Rejected),
+        ];
+        assert_eq!(
+            guesses(&settlements(entries)),
+            vec![
+                (10, Settlement::Pay),
+                (12, Settlement::Supersede),
+                (15, Settlement::Reject),
+            ]
+        );
+    }
+
+    #[test]
+    fn settled_winner_supersedes_the_rest_of_its_round_only() {
+        let mut winner = entry(1, 10, ApprovalStatus::Approved);
+        settle(&mut winner);
+        let mut rejected = entry(2, 3, ApprovalStatus::Rejected);
+        settle(&mut rejected);
+        let entries = vec![
+            winner,
+            entry(1, 12, ApprovalStatus::Pending),
+            rejected,
+            entry(2, 5, ApprovalStatus::Approved),
+        ];
+        assert_eq!(
+            guesses(&settlements(entries)),
+            vec![(12, Settlement::Supersede), (5, Settlement::Pay)]
+        );
+    }
+
+    fn endpoint() -> Endpoint {
+        Endpoint {
+            queue: ApprovalQueue::new("payout_approvals.json".to_string()),
+            token: "s3cret-token".to_string(),
+            hosts: vec!["127.0.0.1:8090".to_string(), "localhost:8090".to_string()],
+        }
+    }
+
+    #[test]
+    fn authorization_requires_the_exact_bearer_token() {
+        assert!(is_authorized(Some("Bearer s3cret-token"), "s3cret-token"));
+        assert!(!is_authorized(Some("Bearer s3cret-tokeN"), "s3cret-token"));
+        assert!(!is_authorized(Some("Bearer s3cret"), "s3cret-token"));
+        assert!(!is_authorized(Some("Basic s3cret-token"), "s3cret-token"));
+        assert!(!is_authorized(None, "s3cret-token"));
+    }
+
+    #[test]
+    fn requests_must_name_the_endpoint_as_host() {
+        let endpoint = endpoint();
+        assert!(endpoint.is_own_origin(Some("127.0.0.1:8090"), None));
+        assert!(endpoint.is_own_origin(Some("LOCALHOST:8090"), None));
+        assert!(!endpoint.is_own_origin(Some("attacker.example:8090"), None));
+        assert!(!endpoint.is_own_origin(None, None));
+    }
+
+    #[test]
+    fn requests_from_another_origin_are_rejected() {
+        let endpoint = endpoint();
+        assert!(endpoint.is_own_origin(Some("127.0.0.1:8090"), Some("http://127.0.0.1:8090")));
+        assert!(!endpoint.is_own_origin(Some("127.0.0.1:8090"), Some("http://attacker.example")));
+        assert!(!endpoint.is_own_origin(Some("127.0.0.1:8090"), Some("null")));
+    }
+}
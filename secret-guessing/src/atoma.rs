@@ -2,7 +2,7 @@ use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
 use base64::engine::{general_purpose::STANDARD, Engine};
 use hkdf::Hkdf;
 use rand::Rng;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::Sha256;
 use thiserror::Error;
 use tracing::{error, info, instrument};
@@ -10,12 +10,19 @@ use x25519_dalek::{PublicKey, StaticSecret};
 
 use crate::types::{
     ChatCompletionRequest, ChatCompletionResponse, ConfidentialComputeRequest,
-    ConfidentialComputeResponse,
+    ConfidentialComputeResponse, CreateEmbeddingRequest, CreateEmbeddingResponse,
 };
 
 /// The header key for the authorization header
 const AUTHORIZATION: &str = "Authorization";
 
+/// The Atoma API endpoint for confidential chat completions
+const CONFIDENTIAL_CHAT_COMPLETIONS_URL: &str =
+    "https://api.atoma.network/v1/confidential/chat/completions";
+
+/// The Atoma API endpoint for confidential embeddings
+const CONFIDENTIAL_EMBEDDINGS_URL: &str = "https://api.atoma.network/v1/confidential/embeddings";
+
 /// The size of the payload hash in bytes
 const PAYLOAD_HASH_SIZE: usize = 32;
 
@@ -34,6 +41,19 @@ const SALT_SIZE: usize = 16;
 /// The result type for the Atoma SDK
 type Result<T> = std::result::Result<T, AtomaSdkError>;
 
+/// A decrypted confidential response, along with the node's proof of its authenticity.
+#[derive(Debug)]
+pub struct SignedResponse<T> {
+    /// The decrypted response body
+    pub body: T,
+
+    /// The node's signature of the response body (base64 encoded), if it signed it
+    pub signature: Option<String>,
+
+    /// The hash of the response body (base64 encoded), if the node sent it
+    pub response_hash: Option<String>,
+}
+
 /// The response structure for the nodes/models/retrieve endpoint
 #[derive(Debug, Deserialize)]
 struct NodesModelsRetrieveResponse {
@@ -162,6 +182,25 @@ impl AtomaSdk {
     /// - Perfect forward secrecy via ephemeral key exchange
     /// - Response integrity verification via hashing
     /// - Response authenticity verification via signatures
+    pub async fn confidential_chat_completions(
+        &self,
+        client_private_key: &StaticSecret,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse> {
+        Ok(self
+            .confidential_chat_completions_signed(client_private_key, request)
+            .await?
+            .body)
+    }
+
+    /// Sends an encrypted chat completion request, like
+    /// [`AtomaSdk::confidential_chat_completions`], keeping the node's signature and hash
+    /// of the response, e.g. for operators to check the verdict a payout relies on.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AtomaSdkError` under the same conditions as
+    /// [`AtomaSdk::confidential_chat_completions`].
     #[instrument(
         level = "info",
         name = "confidential/chat/completions",
@@ -170,11 +209,69 @@ impl AtomaSdk {
             model = self.model,
         )
     )]
-    pub async fn confidential_chat_completions(
+    pub async fn confidential_chat_completions_signed(
         &self,
         client_private_key: &StaticSecret,
         request: ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse> {
+    ) -> Result<SignedResponse<ChatCompletionResponse>> {
+        self.confidential_request(
+            client_private_key,
+            CONFIDENTIAL_CHAT_COMPLETIONS_URL,
+            request,
+        )
+        .await
+    }
+
+    /// Sends an encrypted embeddings request to the Atoma API with end-to-end encryption
+    ///
+    /// The request goes through the same encryption and response verification steps as
+    /// [`AtomaSdk::confidential_chat_completions`], so that the embedded inputs (which
+    /// may contain the secret) are never visible outside of the node's enclave.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_private_key` - The client's X25519 private key for establishing the shared secret
+    /// * `request` - The embeddings request to be encrypted and sent
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the decrypted `CreateEmbeddingResponse` if successful.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AtomaSdkError` under the same conditions as
+    /// [`AtomaSdk::confidential_chat_completions`].
+    #[instrument(
+        level = "info",
+        name = "confidential/embeddings",
+        skip_all,
+        fields(
+            model = self.model,
+        )
+    )]
+    pub async fn confidential_embeddings(
+        &self,
+        client_private_key: &StaticSecret,
+        request: CreateEmbeddingRequest,
+    ) -> Result<CreateEmbeddingResponse> {
+        Ok(self
+            .confidential_request(client_private_key, CONFIDENTIAL_EMBEDDINGS_URL, request)
+            .await?
+            .body)
+    }
+
+    /// Encrypts a request for the node serving the model, sends it to a confidential
+    /// endpoint of the Atoma API, then decrypts and verifies the response.
+    async fn confidential_request<Req, Resp>(
+        &self,
+        client_private_key: &StaticSecret,
+        url: &str,
+        request: Req,
+    ) -> Result<SignedResponse<Resp>>
+    where
+        Req: Serialize,
+        Resp: Serialize + DeserializeOwned,
+    {
         let NodesModelsRetrieveResponse {
             public_key,
             stack_small_id,
@@ -192,7 +289,7 @@ impl AtomaSdk {
                 ))
             })?;
         let node_public_key = PublicKey::from(node_public_key_bytes);
-        let confidential_compute_request = utils::encrypt_chat_completions_request(
+        let confidential_compute_request = utils::encrypt_request(
             request,
             client_private_key,
             &node_public_key,
@@ -204,7 +301,7 @@ impl AtomaSdk {
 
         let client = reqwest::Client::new();
         let response = client
-            .post("https://api.atoma.network/v1/confidential/chat/completions")
+            .post(url)
             .header(AUTHORIZATION, format!("Bearer {}", self.api_key))
             .json(&confidential_compute_request)
             .send()
@@ -231,14 +328,14 @@ impl AtomaSdk {
                 n.len()
             ))
         })?;
-        let response_hash = response_hash
+        let response_hash_bytes = response_hash
             .as_ref()
             .map(|s| STANDARD.decode(s))
             .transpose()?
             .map(|s| s.try_into())
             .transpose()
             .map_err(|_| AtomaSdkError::InvalidPayloadHashLengthError)?;
-        let response_body = utils::decrypt_chat_completions_response(
+        let response_body = utils::decrypt_response(
             response_ciphertext,
             client_private_key,
             &node_public_key,
@@ -247,10 +344,14 @@ impl AtomaSdk {
         )?;
         utils::verify_response_hash_and_signature(
             &response_body,
-            response_hash,
+            response_hash_bytes,
             signature.as_ref().map(|s| s.as_str()),
         )?;
-        Ok(response_body)
+        Ok(SignedResponse {
+            body: response_body,
+            signature,
+            response_hash,
+        })
     }
 }
 
@@ -268,6 +369,9 @@ pub enum AtomaSdkError {
     #[error("Failed to encrypt request: `{0}`")]
     EncryptRequestError(String),
 
+    #[error("Invalid embeddings response: `{0}`")]
+    EmbeddingsResponseError(String),
+
     #[error("Invalid payload hash length")]
     InvalidPayloadHashLengthError,
 
@@ -328,15 +432,15 @@ pub(crate) mod utils {
 
     #[instrument(
         level = "info",
-        name = "encrypt_chat_completions_request",
+        name = "encrypt_request",
         skip_all,
         fields(
             model = model_name,
             stack_small_id = stack_small_id,
         )
     )]
-    pub(crate) fn encrypt_chat_completions_request(
-        request: ChatCompletionRequest,
+    pub(crate) fn encrypt_request<T: Serialize>(
+        request: T,
         client_private_key: &StaticSecret,
         node_public_key: &PublicKey,
         model_name: String,
@@ -370,13 +474,13 @@ pub(crate) mod utils {
         })
     }
 
-    /// Decrypts an encrypted chat completion response using AES-GCM
+    /// Decrypts an encrypted response using AES-GCM
     ///
     /// This function performs the following steps:
     /// 1. Derives a shared secret using Diffie-Hellman key exchange
     /// 2. Generates a symmetric key using HKDF with SHA-256
     /// 3. Decrypts the ciphertext using AES-GCM
-    /// 4. Deserializes the plaintext into a ChatCompletionResponse
+    /// 4. Deserializes the plaintext into the expected response type
     ///
     /// # Arguments
     /// * `ciphertext` - The encrypted response data
@@ -386,7 +490,7 @@ pub(crate) mod utils {
     /// * `salt` - A 16-byte salt used for key derivation
     ///
     /// # Returns
-    /// * `Ok(ChatCompletionResponse)` - The decrypted and deserialized response
+    /// * `Ok(T)` - The decrypted and deserialized response
     /// * `Err(AtomaSdkError)` if:
     ///   - Key derivation fails
     ///   - Decryption fails
@@ -397,14 +501,14 @@ pub(crate) mod utils {
     /// - Perfect forward secrecy via Diffie-Hellman key exchange
     /// - Key derivation using HKDF with SHA-256
     /// - Authenticated encryption using AES-GCM
-    #[instrument(level = "info", name = "decrypt_chat_completions_response", skip_all)]
-    pub(crate) fn decrypt_chat_completions_response(
+    #[instrument(level = "info", name = "decrypt_response", skip_all)]
+    pub(crate) fn decrypt_response<T: DeserializeOwned>(
         ciphertext: Vec<u8>,
         client_private_key: &StaticSecret,
         node_public_key: &PublicKey,
         nonce: [u8; NONCE_SIZE],
         salt: [u8; SALT_SIZE],
-    ) -> Result<ChatCompletionResponse> {
+    ) -> Result<T> {
         let shared_secret = client_private_key.diffie_hellman(&node_public_key);
 
         let hkdf = Hkdf::<Sha256>::new(Some(&salt), shared_secret.as_bytes());
@@ -418,14 +522,14 @@ pub(crate) mod utils {
         Ok(serde_json::from_slice(&plaintext)?)
     }
 
-    /// Verifies the integrity and authenticity of a chat completion response.
+    /// Verifies the integrity and authenticity of a response.
     ///
     /// This function performs two critical security checks:
     /// 1. Verifies that the response hash matches the computed hash of the response body
     /// 2. Validates the cryptographic signature of the response
     ///
     /// # Arguments
-    /// * `response_body` - The decrypted chat completion response to verify
+    /// * `response_body` - The decrypted response to verify
     /// * `response_hash` - Optional Blake2b hash of the response body (32 bytes)
     /// * `signature` - Optional base64-encoded signature of the response hash
     ///
@@ -451,8 +555,8 @@ pub(crate) mod utils {
             signature = ?signature,
         )
     )]
-    pub(crate) fn verify_response_hash_and_signature(
-        response_body: &ChatCompletionResponse,
+    pub(crate) fn verify_response_hash_and_signature<T: Serialize>(
+        response_body: &T,
         response_hash: Option<[u8; PAYLOAD_HASH_SIZE]>,
         signature: Option<&str>,
     ) -> Result<()> {
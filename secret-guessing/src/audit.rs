@@ -0,0 +1,75 @@
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::{base_types::SuiAddress, event::EventID};
+
+use crate::{config::SecretGuessingConfig, quorum::JudgeVote, store::StoreError};
+
+/// The default file path for the judging audit log
+const DEFAULT_AUDIT_LOG_PATH: &str = "judging_audit.jsonl";
+
+/// The record of how a guess was judged, kept for the audit trail.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JudgingRecord {
+    /// The on-chain identifier of the `NewGuessEvent`
+    pub event_id: EventID,
+
+    /// The player who submitted the guess
+    pub sender: SuiAddress,
+
+    /// The guess itself
+    pub guess: String,
+
+    /// The final verdict on the guess
+    pub is_correct: bool,
+
+    /// Whether the guess was escalated for review, because the judges were split
+    pub escalated: bool,
+
+    /// The layer of the judging pipeline that reached the verdict
+    pub decided_by: String,
+
+    /// Explanation of the final verdict
+    pub explanation: String,
+
+    /// The votes of each judge, empty if the verdict was reached without the model
+    pub votes: Vec<JudgeVote>,
+
+    /// Unix timestamp (in seconds) at which the verdict was committed
+    pub judged_at: u64,
+}
+
+/// An append-only, JSON lines log of every judged guess.
+pub struct AuditLog {
+    /// File path of the audit log
+    path: String,
+}
+
+impl AuditLog {
+    /// Constructor
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+
+    /// Creates the audit log from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        Self::new(
+            config
+                .audit_log_path
+                .clone()
+                .unwrap_or_else(|| DEFAULT_AUDIT_LOG_PATH.to_string()),
+        )
+    }
+
+    /// Appends a record to the audit log.
+    pub fn append<T: Serialize>(&self, record: &T) -> Result<(), StoreError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        Ok(())
+    }
+}
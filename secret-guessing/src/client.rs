@@ -1,26 +1,57 @@
-use std::str::FromStr;
-
 use sui_sdk::{
-    json::SuiJsonValue,
+    rpc_types::{
+        BalanceChange, OwnedObjectRef, SuiEvent, SuiExecutionStatus, SuiObjectDataFilter,
+        SuiObjectDataOptions, SuiObjectResponseQuery, SuiParsedData, SuiTransactionBlockEffectsAPI,
+        SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+        SuiTransactionBlockResponseQuery, TransactionFilter,
+    },
     types::{
-        base_types::{ObjectID, ObjectIDParseError, SuiAddress},
+        base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress},
+        digests::TransactionDigest,
         error::SuiError,
+        gas::GasCostSummary,
+        gas_coin::GAS,
+        object::Owner,
+        parse_sui_struct_tag,
+        programmable_transaction_builder::ProgrammableTransactionBuilder,
+        quorum_driver_types::ExecuteTransactionRequestType,
+        transaction::{ObjectArg, TransactionData},
     },
     wallet_context::WalletContext,
+    SuiClient,
 };
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 use x25519_dalek::PublicKey;
 
-use crate::SECRET_GUESSING_MODULE_NAME;
+use crate::{
+    gas::{GasManager, GasRebalance},
+    transactions::{self, SecretGuessingCall, ADMIN_CAP_STRUCT_NAME},
+    SECRET_GUESSING_MODULE_NAME,
+};
+
+/// The gas budget of the transactions merging or splitting gas coins
+const GAS_REBALANCE_BUDGET: u64 = 10_000_000; // 0.01 SUI
+
+/// The name of the database field holding the treasury pool
+const TREASURY_POOL_FIELD: &str = "treasury_pool";
+
+/// The name of the database field holding the fee of a guess
+const FEE_FIELD: &str = "fee";
+
+/// The name of the database field holding the number of guesses
+const GUESS_COUNT_FIELD: &str = "guess_count";
 
-/// The gas budget for the node registration transaction
-const GAS_BUDGET: u64 = 50_000_000; // 0.05 SUI
+/// The name of the database field holding the epoch of the latest TDX quote rotation
+const EPOCH_FIELD: &str = "epoch";
 
-/// The name of the function to withdraw funds from the treasury pool
-const WITHDRAW_FUNDS_FROM_TREASURY_POOL_FUNCTION_NAME: &str = "withdraw_funds_from_treasury_pool";
+/// The name of the database field holding the agent's registered public key
+const PUBLIC_KEY_FIELD: &str = "public_key_bytes";
 
-/// The name of the function to submit the node public key
-const RESUBMIT_TDX_ATTESTATION_FUNCTION_NAME: &str = "resubmit_tdx_attestation";
+/// The name of the database field holding the TDX quote attesting the public key
+const TDX_QUOTE_FIELD: &str = "tdx_quote_v4";
+
+/// The number of the most recent payouts searched for a given winner
+const FIND_PAYOUT_LIMIT: usize = 50;
 
 /// The result type for the Sui client
 type Result<T> = std::result::Result<T, SuiClientError>;
@@ -31,11 +62,18 @@ pub struct SuiClientContext {
     /// The ID of the Secret Guessing database object
     secret_guessing_db: ObjectID,
 
+    /// The version at which the Secret Guessing database object was shared, fetched on
+    /// the first transaction
+    secret_guessing_db_initial_shared_version: Option<SequenceNumber>,
+
     /// The ID of the Secret Guessing package
     secret_guessing_package_id: ObjectID,
 
     /// The wallet context for the current Sui client
     wallet_context: WalletContext,
+
+    /// The gas coins paying for the transactions
+    gas_manager: GasManager,
 }
 
 impl SuiClientContext {
@@ -47,11 +85,80 @@ impl SuiClientContext {
     ) -> Self {
         Self {
             secret_guessing_db,
+            secret_guessing_db_initial_shared_version: None,
             secret_guessing_package_id,
             wallet_context,
+            gas_manager: GasManager::default(),
+        }
+    }
+
+    /// Sets the gas manager paying for the transactions, e.g. built with
+    /// [`GasManager::from_config`].
+    pub fn with_gas_manager(mut self, gas_manager: GasManager) -> Self {
+        self.gas_manager = gas_manager;
+        self
+    }
+
+    /// The ID of the Secret Guessing package.
+    pub fn package_id(&self) -> ObjectID {
+        self.secret_guessing_package_id
+    }
+
+    /// Returns the address of the wallet's active account.
+    pub fn active_address(&mut self) -> Result<SuiAddress> {
+        self.wallet_context
+            .active_address()
+            .map_err(SuiClientError::WalletError)
+    }
+
+    /// Returns a client of the wallet's Sui RPC node.
+    pub async fn sui_client(&self) -> Result<SuiClient> {
+        self.wallet_context
+            .get_client()
+            .await
+            .map_err(SuiClientError::WalletError)
+    }
+
+    /// Reads the state of the game from the Secret Guessing database object.
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::MissingObjectContent` - If the object has no Move content
+    /// * `SuiClientError::InvalidGameState` - If a field is missing or has an unexpected type
+    /// * `SuiClientError::RpcError` - If the object cannot be fetched
+    #[instrument(level = "info", skip_all)]
+    pub async fn fetch_game_state(&self) -> Result<GameState> {
+        let client = self.sui_client().await?;
+        let response = client
+            .read_api()
+            .get_object_with_options(
+                self.secret_guessing_db,
+                SuiObjectDataOptions::new().with_content(),
+            )
+            .await?;
+        match response.data.and_then(|data| data.content) {
+            Some(SuiParsedData::MoveObject(object)) => {
+                GameState::from_fields(&object.fields.to_json_value())
+            }
+            _ => Err(SuiClientError::MissingObjectContent(
+                self.secret_guessing_db,
+            )),
         }
     }
 
+    /// Registers the node's public key on-chain, along with the TDX quote attesting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - The node's X25519 public key
+    /// * `tdx_quote_bytes` - The TDX quote attesting the public key
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
     #[instrument(
         level = "info",
         skip_all,
@@ -66,60 +173,41 @@ impl SuiClientContext {
         gas: Option<ObjectID>,
         gas_budget: Option<u64>,
         gas_price: Option<u64>,
-    ) -> Result<String> {
-        let client = self.wallet_context.get_client().await?;
-        let active_address = self.wallet_context.active_address()?;
-
-        let tx = client
-            .transaction_builder()
-            .move_call(
-                active_address,
-                self.secret_guessing_package_id,
-                SECRET_GUESSING_MODULE_NAME,
-                RESUBMIT_TDX_ATTESTATION_FUNCTION_NAME,
-                vec![],
-                vec![
-                    SuiJsonValue::from_object_id(self.secret_guessing_db),
-                    SuiJsonValue::new(public_key.to_bytes().into())?,
-                    SuiJsonValue::new(tdx_quote_bytes.into())?,
-                ],
-                gas,
-                gas_budget.unwrap_or(GAS_BUDGET),
-                gas_price,
-            )
-            .await?;
-
-        let tx = self.wallet_context.sign_transaction(&tx);
-        let response = self
-            .wallet_context
-            .execute_transaction_must_succeed(tx)
-            .await;
-
-        Ok(response.digest.to_string())
+    ) -> Result<TxOutcome> {
+        self.execute_calls(
+            vec![SecretGuessingCall::ResubmitTdxAttestation {
+                public_key: public_key.to_bytes().to_vec(),
+                tdx_quote: tdx_quote_bytes,
+            }],
+            gas,
+            gas_budget,
+            gas_price,
+        )
+        .await
     }
 
-    /// Withdraws funds from the treasury pool and transfers them to the specified winner address.
+    /// Prepares the withdrawal of the treasury pool to the specified winner address,
+    /// without sending it.
     ///
-    /// This method executes a Move call to withdraw funds from the Secret Guessing game's treasury pool
-    /// and transfer them to the winning player's address.
+    /// The payout is prepared and then executed with [`Self::execute_prepared`], so that
+    /// its digest can be journaled before it is sent.
     ///
     /// # Arguments
     ///
     /// * `winner_address` - The Sui address of the winning player who will receive the funds
-    /// * `gas` - Optional ObjectID to use for gas payment. If None, the system will select an appropriate gas object
-    /// * `gas_budget` - Optional gas budget for the transaction. Defaults to 50,000,000 (0.05 SUI) if None
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
     /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
     ///
     /// # Returns
     ///
-    /// Returns a `Result<String>` containing the transaction digest if successful, or a `SuiClientError` if the operation fails
+    /// Returns a `Result<PreparedTransaction>` containing the unsigned payout transaction if successful, or a `SuiClientError` if the operation fails
     ///
     /// # Errors
     ///
     /// This function will return an error if:
     /// * The wallet context fails to get the active address
-    /// * The object ID parsing fails
-    /// * The transaction execution fails
+    /// * The transaction fails in simulation, e.g. with a Move abort
     #[instrument(
         level = "info",
         skip_all,
@@ -127,56 +215,1054 @@ impl SuiClientContext {
             winner_address = %winner_address,
         )
     )]
-    pub async fn withdraw_funds_from_treasury_pool(
+    pub async fn prepare_withdraw_funds_from_treasury_pool(
         &mut self,
         winner_address: SuiAddress,
         gas: Option<ObjectID>,
         gas_budget: Option<u64>,
         gas_price: Option<u64>,
-    ) -> Result<String> {
-        let client = self.wallet_context.get_client().await?;
-        let active_address = self.wallet_context.active_address()?;
-
-        let tx = client
-            .transaction_builder()
-            .move_call(
-                active_address,
-                self.secret_guessing_package_id,
-                SECRET_GUESSING_MODULE_NAME,
-                WITHDRAW_FUNDS_FROM_TREASURY_POOL_FUNCTION_NAME,
-                vec![],
+    ) -> Result<PreparedTransaction> {
+        self.prepare_calls(
+            vec![SecretGuessingCall::WithdrawFundsFromTreasuryPool {
+                winner: winner_address,
+            }],
+            gas,
+            gas_budget,
+            gas_price,
+        )
+        .await
+    }
+
+    /// Prepares the payout of the given amounts out of the treasury pool, without sending
+    /// it, e.g. when several winners split the pool or a house fee is taken. What is not
+    /// paid out stays in the pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `recipients` - The addresses paid out of the treasury pool, along with their amount in MIST
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<PreparedTransaction>` containing the unsigned payout transaction if successful, or a `SuiClientError` if the operation fails
+    #[instrument(level = "info", skip_all, fields(num_recipients = recipients.len()))]
+    pub async fn prepare_withdraw_payouts(
+        &mut self,
+        recipients: &[(SuiAddress, u64)],
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<PreparedTransaction> {
+        let (recipients, amounts) = recipients.iter().copied().unzip();
+        self.prepare_calls(
+            vec![SecretGuessingCall::WithdrawPayouts {
+                recipients,
+                amounts,
+            }],
+            gas,
+            gas_budget,
+            gas_price,
+        )
+        .await
+    }
+
+    /// Publishes the commitment to the secret of a new round.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitment` - The salted hash of the secret, see [`crate::commitment::compute_commitment`]
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    #[instrument(level = "info", skip_all)]
+    pub async fn commit_secret(
+        &mut self,
+        commitment: Vec<u8>,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let outcome = self
+            .execute_calls(
+                vec![SecretGuessingCall::CommitSecret { commitment }],
+                gas,
+                gas_budget,
+                gas_price,
+            )
+            .await?;
+
+        info!(
+            target = "sui-client-commit-secret",
+            tx_hash = %outcome.digest,
+            "Successfully committed to the secret"
+        );
+
+        Ok(outcome)
+    }
+
+    /// Registers the node's public key and publishes the commitment to the secret of a new
+    /// round, in a single transaction.
+    ///
+    /// Both calls take effect together or not at all, so a round never starts with a
+    /// commitment made by a node whose attestation was not accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - The node's X25519 public key
+    /// * `tdx_quote_bytes` - The TDX quote attesting the public key
+    /// * `commitment` - The salted hash of the secret, see [`crate::commitment::compute_commitment`]
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    #[instrument(
+        level = "info",
+        skip_all,
+        fields(
+            public_key = ?public_key,
+        )
+    )]
+    pub async fn submit_node_public_key_and_commit_secret(
+        &mut self,
+        public_key: PublicKey,
+        tdx_quote_bytes: Vec<u8>,
+        commitment: Vec<u8>,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let outcome = self
+            .execute_calls(
                 vec![
-                    SuiJsonValue::from_object_id(self.secret_guessing_db),
-                    SuiJsonValue::from_object_id(ObjectID::from_str(
-                        winner_address.to_string().as_str(),
-                    )?),
+                    SecretGuessingCall::ResubmitTdxAttestation {
+                        public_key: public_key.to_bytes().to_vec(),
+                        tdx_quote: tdx_quote_bytes,
+                    },
+                    SecretGuessingCall::CommitSecret { commitment },
                 ],
                 gas,
-                gas_budget.unwrap_or(GAS_BUDGET),
+                gas_budget,
                 gas_price,
             )
             .await?;
 
         info!(
-            target = "sui-client-withdraw-funds-from-treasury-pool",
-            tx_hash = %tx.digest(),
-            winner_address = %winner_address,
-            "Withdrew funds from treasury pool for winner"
+            target = "sui-client-commit-secret",
+            tx_hash = %outcome.digest,
+            "Successfully submitted the node public key and committed to the secret"
         );
 
-        let tx = self.wallet_context.sign_transaction(&tx);
-        let response = self
-            .wallet_context
-            .execute_transaction_must_succeed(tx)
-            .await;
+        Ok(outcome)
+    }
+
+    /// Reveals the secret of a round, along with the salt of its commitment, so that
+    /// anyone can verify it against the commitment published when the round started.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret of the round
+    /// * `salt` - The salt of the secret's commitment
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    #[instrument(level = "info", skip_all)]
+    pub async fn reveal_secret(
+        &mut self,
+        secret: &str,
+        salt: Vec<u8>,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let outcome = self
+            .execute_calls(
+                vec![SecretGuessingCall::RevealSecret {
+                    secret: secret.as_bytes().to_vec(),
+                    salt,
+                }],
+                gas,
+                gas_budget,
+                gas_price,
+            )
+            .await?;
+
+        info!(
+            target = "sui-client-reveal-secret",
+            tx_hash = %outcome.digest,
+            "Successfully revealed the secret"
+        );
+
+        Ok(outcome)
+    }
+
+    /// Looks up the given transactions on-chain.
+    ///
+    /// # Returns
+    ///
+    /// The outcomes of the transactions that were executed, successfully or not. The
+    /// transactions that are unknown to the RPC node are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SuiClientError::RpcError` if the RPC node cannot be reached, so that an
+    /// unreachable node is never mistaken for a transaction that was not executed.
+    pub async fn executed_transactions(
+        &self,
+        digests: &[TransactionDigest],
+    ) -> Result<Vec<TxOutcome>> {
+        if digests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let client = self.sui_client().await?;
+        let responses = client
+            .read_api()
+            .multi_get_transactions_with_options(
+                digests.to_vec(),
+                SuiTransactionBlockResponseOptions::new()
+                    .with_effects()
+                    .with_balance_changes(),
+            )
+            .await?;
+        Ok(responses
+            .into_iter()
+            .filter(|response| response.effects.is_some())
+            .map(TxOutcome::from)
+            .collect())
+    }
+
+    /// Finds a treasury payout to the given winner, executed within the given time range.
+    ///
+    /// Payouts are looked up from the most recent one, so the search is cheap as long as
+    /// the range starts recently.
+    ///
+    /// # Arguments
+    ///
+    /// * `winner` - The address of the winner
+    /// * `from_ms` - The earliest timestamp of the payout, in milliseconds
+    /// * `to_ms` - The latest timestamp of the payout, in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// The outcome of the payout crediting the winner, if any.
+    pub async fn find_treasury_payout(
+        &self,
+        winner: SuiAddress,
+        from_ms: u64,
+        to_ms: u64,
+    ) -> Result<Option<TxOutcome>> {
+        let client = self.sui_client().await?;
+        // The whole pool and the amounts computed by a payout policy are withdrawn by
+        // different functions
+        for function in [
+            transactions::WITHDRAW_FUNDS_FROM_TREASURY_POOL_FUNCTION_NAME,
+            transactions::WITHDRAW_PAYOUTS_FUNCTION_NAME,
+        ] {
+            let query = SuiTransactionBlockResponseQuery::new(
+                Some(TransactionFilter::MoveFunction {
+                    package: self.secret_guessing_package_id,
+                    module: Some(SECRET_GUESSING_MODULE_NAME.to_string()),
+                    function: Some(function.to_string()),
+                }),
+                Some(SuiTransactionBlockResponseOptions::new().with_balance_changes()),
+            );
+            let page = client
+                .read_api()
+                .query_transaction_blocks(query, None, Some(FIND_PAYOUT_LIMIT), true)
+                .await?;
+            let payout = page
+                .data
+                .into_iter()
+                .take_while(|tx| tx.timestamp_ms.is_none_or(|ts| ts >= from_ms))
+                .filter(|tx| tx.timestamp_ms.is_some_and(|ts| ts <= to_ms))
+                .map(TxOutcome::from)
+                .find(|outcome| outcome.sui_received_by(winner) > 0);
+            if payout.is_some() {
+                return Ok(payout);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the admin capability of the Secret Guessing package owned by the active address.
+    ///
+    /// The capability is looked up before each admin operation, as its version changes
+    /// with every transaction using it, and it changes hands when the admin is rotated.
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::MissingAdminCap` - If the active address owns no admin capability
+    /// * `SuiClientError::RpcError` - If the owned objects cannot be fetched
+    pub async fn admin_cap(&mut self) -> Result<ObjectRef> {
+        let owner = self.active_address()?;
+        let client = self.sui_client().await?;
+        let cap_type = parse_sui_struct_tag(&format!(
+            "{}::{SECRET_GUESSING_MODULE_NAME}::{ADMIN_CAP_STRUCT_NAME}",
+            self.secret_guessing_package_id
+        ))
+        .map_err(SuiClientError::BuildTransactionError)?;
+        let page = client
+            .read_api()
+            .get_owned_objects(
+                owner,
+                Some(SuiObjectResponseQuery::new_with_filter(
+                    SuiObjectDataFilter::StructType(cap_type),
+                )),
+                None,
+                Some(1),
+            )
+            .await?;
+        page.data
+            .into_iter()
+            .find_map(|response| response.data)
+            .map(|data| data.object_ref())
+            .ok_or(SuiClientError::MissingAdminCap(owner))
+    }
+
+    /// Pauses or unpauses the game. While paused, guesses are refused.
+    ///
+    /// The transaction is signed by the active address, which must own the admin capability.
+    ///
+    /// # Arguments
+    ///
+    /// * `paused` - Whether to pause the game
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::MissingAdminCap` - If the active address owns no admin capability
+    /// * Any error of [`Self::execute_calls`]
+    #[instrument(level = "info", skip_all)]
+    pub async fn set_paused(
+        &mut self,
+        paused: bool,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let cap = self.admin_cap().await?;
+        let outcome = self
+            .execute_calls(
+                vec![SecretGuessingCall::SetPaused { cap, paused }],
+                gas,
+                gas_budget,
+                gas_price,
+            )
+            .await?;
+
+        info!(
+            target = "sui-client-admin",
+            tx_hash = %outcome.digest,
+            paused,
+            "Successfully changed whether the game is paused"
+        );
+
+        Ok(outcome)
+    }
+
+    /// Changes the fee of a guess.
+    ///
+    /// The transaction is signed by the active address, which must own the admin capability.
+    ///
+    /// # Arguments
+    ///
+    /// * `fee` - The new fee of a guess, in MIST
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::MissingAdminCap` - If the active address owns no admin capability
+    /// * Any error of [`Self::execute_calls`]
+    #[instrument(level = "info", skip_all)]
+    pub async fn set_fee(
+        &mut self,
+        fee: u64,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let cap = self.admin_cap().await?;
+        let outcome = self
+            .execute_calls(
+                vec![SecretGuessingCall::SetFee { cap, fee }],
+                gas,
+                gas_budget,
+                gas_price,
+            )
+            .await?;
 
         info!(
-            target = "sui-client-withdraw-funds-from-treasury-pool",
-            tx_hash = %response.digest,
-            "Successfully withdrew funds from treasury pool for winner"
+            target = "sui-client-admin",
+            tx_hash = %outcome.digest,
+            fee,
+            "Successfully changed the fee of a guess"
         );
 
-        Ok(response.digest.to_string())
+        Ok(outcome)
+    }
+
+    /// Adds funds from the active address to the treasury pool.
+    ///
+    /// The transaction is signed by the active address, which must own the admin capability.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount added to the treasury pool, in MIST
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::MissingAdminCap` - If the active address owns no admin capability
+    /// * Any error of [`Self::execute_calls`]
+    #[instrument(level = "info", skip_all)]
+    pub async fn seed_treasury(
+        &mut self,
+        amount: u64,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let cap = self.admin_cap().await?;
+        let outcome = self
+            .execute_calls(
+                vec![SecretGuessingCall::SeedTreasury { cap, amount }],
+                gas,
+                gas_budget,
+                gas_price,
+            )
+            .await?;
+
+        info!(
+            target = "sui-client-admin",
+            tx_hash = %outcome.digest,
+            amount,
+            "Successfully seeded the treasury pool"
+        );
+
+        Ok(outcome)
+    }
+
+    /// Hands the admin capability over to a new admin.
+    ///
+    /// The transaction is signed by the active address, which must own the admin capability.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_admin` - The address of the new admin
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the system will use the network's reference price
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<TxOutcome>` containing the executed transaction's outcome if successful, or a `SuiClientError` if the operation fails
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::MissingAdminCap` - If the active address owns no admin capability
+    /// * Any error of [`Self::execute_calls`]
+    #[instrument(level = "info", skip_all)]
+    pub async fn rotate_admin(
+        &mut self,
+        new_admin: SuiAddress,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let cap = self.admin_cap().await?;
+        let outcome = self
+            .execute_calls(
+                vec![SecretGuessingCall::RotateAdmin { cap, new_admin }],
+                gas,
+                gas_budget,
+                gas_price,
+            )
+            .await?;
+
+        info!(
+            target = "sui-client-admin",
+            tx_hash = %outcome.digest,
+            new_admin = %new_admin,
+            "Successfully rotated the admin"
+        );
+
+        Ok(outcome)
+    }
+
+    /// Runs calls to the Secret Guessing module in a single programmable transaction,
+    /// paying gas from the gas manager's pool.
+    ///
+    /// The calls are executed in order and atomically: if any of them aborts, none of
+    /// them takes effect. The wallet balance is checked first. Unless a gas budget is
+    /// given, it is estimated from a dry-run of the transaction, with a safety margin.
+    /// Unless a gas coin is given, the smallest coin of the pool that can pay the budget is
+    /// selected, merging or splitting the wallet's coins first if needed. The coin must
+    /// also cover what the calls pay out of it, e.g. the fee of a guess.
+    ///
+    /// # Arguments
+    ///
+    /// * `calls` - The calls to run, e.g. an attestation resubmission followed by a secret commitment
+    /// * `gas` - Optional ObjectID to use for gas payment. If None, a coin is selected from the gas manager's pool
+    /// * `gas_budget` - Optional gas budget for the transaction. If None, it is estimated from a dry-run
+    /// * `gas_price` - Optional gas price for the transaction. If None, the network's reference price is used
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::LowGasBalance` - If the wallet balance is below the halt threshold
+    /// * `SuiClientError::NoGasCoin` - If no coin can pay the gas budget, or the minimum budget of the dry-run
+    /// * `SuiClientError::GasCoinNotFound` - If the given gas coin is not owned by the wallet
+    /// * Any error of [`Self::execute_transaction`]
+    #[instrument(level = "info", skip_all, fields(function = transactions::function_name(&calls)))]
+    pub async fn execute_calls(
+        &mut self,
+        calls: Vec<SecretGuessingCall>,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<TxOutcome> {
+        let prepared = self
+            .prepare_calls(calls, gas, gas_budget, gas_price)
+            .await?;
+        self.execute_prepared(prepared).await
+    }
+
+    /// Builds the transaction of [`Self::execute_calls`], without sending it.
+    ///
+    /// The gas budget is estimated and the gas coin selected as for
+    /// [`Self::execute_calls`], so that the digest of the transaction is known before it
+    /// is executed with [`Self::execute_prepared`].
+    ///
+    /// # Errors
+    ///
+    /// Any error of [`Self::execute_calls`], except those of the execution itself
+    #[instrument(level = "info", skip_all, fields(function = transactions::function_name(&calls)))]
+    pub async fn prepare_calls(
+        &mut self,
+        calls: Vec<SecretGuessingCall>,
+        gas: Option<ObjectID>,
+        gas_budget: Option<u64>,
+        gas_price: Option<u64>,
+    ) -> Result<PreparedTransaction> {
+        let function = transactions::function_name(&calls);
+        let payment = transactions::payment(&calls);
+        let client = self.sui_client().await?;
+        let active_address = self.active_address()?;
+        self.gas_manager.refresh(&client, active_address).await?;
+
+        let db = self.secret_guessing_db_arg().await?;
+        let pt = transactions::build_programmable_transaction(
+            &calls,
+            self.secret_guessing_package_id,
+            db,
+        )
+        .map_err(SuiClientError::BuildTransactionError)?;
+        let gas_price = match gas_price {
+            Some(gas_price) => gas_price,
+            None => client.read_api().get_reference_gas_price().await?,
+        };
+
+        // The dry-run estimating the gas budget also catches Move aborts, so the
+        // transaction is not simulated a second time before being executed
+        let (gas_budget, simulated) = match gas_budget {
+            Some(gas_budget) => (gas_budget, false),
+            None => {
+                let no_gas_coin = SuiClientError::NoGasCoin {
+                    budget: self.gas_manager.min_budget().saturating_add(payment),
+                };
+                let probe_coin = match gas {
+                    Some(gas) => self.gas_manager.coin_ref(gas),
+                    None => self.gas_manager.largest_coin(),
+                };
+                let (Some(probe_coin), Some(probe_budget)) =
+                    (probe_coin, self.gas_manager.probe_budget(payment))
+                else {
+                    return Err(no_gas_coin);
+                };
+                let probe = TransactionData::new_programmable(
+                    active_address,
+                    vec![probe_coin],
+                    pt.clone(),
+                    probe_budget,
+                    gas_price,
+                );
+                let dry_run = client.read_api().dry_run_transaction_block(probe).await?;
+                if let SuiExecutionStatus::Failure { error } = dry_run.effects.status() {
+                    return Err(SuiClientError::DryRunFailed {
+                        function,
+                        failure: ExecutionFailure::parse(error),
+                    });
+                }
+                let gas_budget = self
+                    .gas_manager
+                    .estimate_budget(dry_run.effects.gas_cost_summary());
+                (gas_budget, true)
+            }
+        };
+
+        let gas = match gas {
+            Some(gas) => self
+                .gas_manager
+                .coin_ref(gas)
+                .ok_or(SuiClientError::GasCoinNotFound(gas))?,
+            None => {
+                self.select_gas_coin(active_address, gas_budget.saturating_add(payment))
+                    .await?
+            }
+        };
+        let tx =
+            TransactionData::new_programmable(active_address, vec![gas], pt, gas_budget, gas_price);
+        Ok(PreparedTransaction {
+            tx,
+            function,
+            simulated,
+        })
+    }
+
+    /// Signs and executes a transaction built by [`Self::prepare_calls`].
+    ///
+    /// # Errors
+    ///
+    /// Any error of [`Self::execute_transaction`]
+    pub async fn execute_prepared(&mut self, prepared: PreparedTransaction) -> Result<TxOutcome> {
+        Ok(TxOutcome::from(
+            self.execute_transaction(prepared.tx, prepared.function, prepared.simulated)
+                .await?,
+        ))
+    }
+
+    /// Returns the shared Secret Guessing database object, as a mutable transaction input.
+    ///
+    /// The version at which the object was shared is fetched once, and then cached.
+    async fn secret_guessing_db_arg(&mut self) -> Result<ObjectArg> {
+        let initial_shared_version = match self.secret_guessing_db_initial_shared_version {
+            Some(version) => version,
+            None => {
+                let client = self.sui_client().await?;
+                let response = client
+                    .read_api()
+                    .get_object_with_options(
+                        self.secret_guessing_db,
+                        SuiObjectDataOptions::new().with_owner(),
+                    )
+                    .await?;
+                let Some(Owner::Shared {
+                    initial_shared_version,
+                }) = response.data.and_then(|data| data.owner)
+                else {
+                    return Err(SuiClientError::NotSharedObject(self.secret_guessing_db));
+                };
+                self.secret_guessing_db_initial_shared_version = Some(initial_shared_version);
+                initial_shared_version
+            }
+        };
+        Ok(ObjectArg::SharedObject {
+            id: self.secret_guessing_db,
+            initial_shared_version,
+            mutable: true,
+        })
+    }
+
+    /// Selects a gas coin able to pay the given budget, merging or splitting the wallet's
+    /// coins first if the pool needs it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SuiClientError::NoGasCoin` if no coin can pay the budget, even after
+    /// merging the coins.
+    async fn select_gas_coin(&mut self, owner: SuiAddress, budget: u64) -> Result<ObjectRef> {
+        if let Some(rebalance) = self.gas_manager.plan_rebalance(budget) {
+            // The pool is left as is if the rebalance fails, as a coin may still pay the budget
+            match self.rebalance_gas_coins(owner, &rebalance).await {
+                Ok(outcome) => GasManager::log_rebalance(&rebalance, &outcome.digest),
+                Err(e) => warn!(
+                    target = "gas-manager",
+                    "Failed to rebalance the gas coins: {e}"
+                ),
+            }
+            let client = self.sui_client().await?;
+            self.gas_manager.refresh(&client, owner).await?;
+        }
+        self.gas_manager
+            .select_coin(budget)
+            .ok_or(SuiClientError::NoGasCoin { budget })
+    }
+
+    /// Merges or splits the wallet's gas coins.
+    async fn rebalance_gas_coins(
+        &mut self,
+        owner: SuiAddress,
+        rebalance: &GasRebalance,
+    ) -> Result<TxOutcome> {
+        let client = self.sui_client().await?;
+        let gas_price = client.read_api().get_reference_gas_price().await?;
+        let tx = match rebalance {
+            GasRebalance::Merge(coins) => client
+                .transaction_builder()
+                .pay_all_sui(
+                    owner,
+                    coins.iter().map(|coin| coin.0).collect(),
+                    owner,
+                    GAS_REBALANCE_BUDGET,
+                )
+                .await
+                .map_err(SuiClientError::BuildTransactionError)?,
+            GasRebalance::Split { coin, amounts } => {
+                // The new coins are split from the gas coin itself
+                let mut builder = ProgrammableTransactionBuilder::new();
+                builder
+                    .pay_sui(vec![owner; amounts.len()], amounts.clone())
+                    .map_err(SuiClientError::BuildTransactionError)?;
+                TransactionData::new_programmable(
+                    owner,
+                    vec![*coin],
+                    builder.finish(),
+                    GAS_REBALANCE_BUDGET,
+                    gas_price,
+                )
+            }
+        };
+        Ok(TxOutcome::from(
+            self.execute_transaction(tx, "rebalance_gas_coins", false)
+                .await?,
+        ))
+    }
+
+    /// Dry-runs, signs and executes a transaction, returning its effects.
+    ///
+    /// Unless the caller already simulated the same calls (e.g. to estimate the gas
+    /// budget), the transaction is first simulated, so that a Move abort is caught before
+    /// any gas is spent. Failures, in simulation or on-chain, are returned as errors, with
+    /// the location and code of Move aborts, and never panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The unsigned transaction
+    /// * `function` - The name of the Move function called, for error reporting
+    /// * `simulated` - Whether the calls of the transaction were already dry-run successfully
+    ///
+    /// # Returns
+    ///
+    /// Returns the executed transaction, along with its effects, events, object changes
+    /// and balance changes.
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::DryRunFailed` - If the transaction fails in simulation
+    /// * `SuiClientError::TransactionFailed` - If the transaction is executed, but fails on-chain
+    /// * `SuiClientError::RpcError` - If the RPC node cannot be reached
+    #[instrument(level = "info", skip_all, fields(function = %function, tx_hash = %tx.digest()))]
+    async fn execute_transaction(
+        &mut self,
+        tx: TransactionData,
+        function: &'static str,
+        simulated: bool,
+    ) -> Result<SuiTransactionBlockResponse> {
+        let client = self.sui_client().await?;
+
+        if !simulated {
+            let dry_run = client
+                .read_api()
+                .dry_run_transaction_block(tx.clone())
+                .await?;
+            if let SuiExecutionStatus::Failure { error } = dry_run.effects.status() {
+                let failure = ExecutionFailure::parse(error);
+                error!(
+                    target = "sui-client",
+                    function = %function,
+                    "Transaction failed in simulation, not executing it: {failure}"
+                );
+                return Err(SuiClientError::DryRunFailed { function, failure });
+            }
+        }
+
+        let tx = self.wallet_context.sign_transaction(&tx);
+        let response = client
+            .quorum_driver_api()
+            .execute_transaction_block(
+                tx,
+                SuiTransactionBlockResponseOptions::new()
+                    .with_effects()
+                    .with_events()
+                    .with_object_changes()
+                    .with_balance_changes(),
+                Some(ExecuteTransactionRequestType::WaitForLocalExecution),
+            )
+            .await?;
+        if let Some(SuiExecutionStatus::Failure { error }) =
+            response.effects.as_ref().map(|effects| effects.status())
+        {
+            let failure = ExecutionFailure::parse(error);
+            error!(
+                target = "sui-client",
+                function = %function,
+                tx_hash = %response.digest,
+                "Transaction failed on-chain: {failure}"
+            );
+            return Err(SuiClientError::TransactionFailed {
+                function,
+                tx_hash: response.digest.to_string(),
+                failure,
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+/// The state of the game, as stored in the Secret Guessing database object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameState {
+    /// The balance of the treasury pool, in MIST
+    pub treasury_pool_balance: u64,
+
+    /// The fee of a guess, in MIST
+    pub fee: u64,
+
+    /// The number of guesses made so far
+    pub guess_count: u64,
+
+    /// The epoch of the latest TDX quote rotation
+    pub epoch: u64,
+
+    /// The agent's registered X25519 public key, empty until the first attestation
+    pub public_key: Vec<u8>,
+
+    /// The TDX quote attesting the registered public key
+    pub tdx_quote: Vec<u8>,
+}
+
+impl GameState {
+    /// Decodes the game state from the fields of the database object, rendered as JSON.
+    ///
+    /// 64-bit integers are rendered as strings, balances either as their value or as a
+    /// struct holding it, and byte vectors as arrays of numbers.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SuiClientError::InvalidGameState` if a field is missing or has an unexpected type.
+    pub fn from_fields(fields: &serde_json::Value) -> Result<Self> {
+        let u64_field = |name: &'static str| {
+            fields
+                .get(name)
+                .and_then(json_u64)
+                .ok_or(SuiClientError::InvalidGameState(name))
+        };
+        let bytes_field = |name: &'static str| {
+            fields
+                .get(name)
+                .and_then(json_bytes)
+                .ok_or(SuiClientError::InvalidGameState(name))
+        };
+        Ok(Self {
+            treasury_pool_balance: u64_field(TREASURY_POOL_FIELD)?,
+            fee: u64_field(FEE_FIELD)?,
+            guess_count: u64_field(GUESS_COUNT_FIELD)?,
+            epoch: u64_field(EPOCH_FIELD)?,
+            public_key: bytes_field(PUBLIC_KEY_FIELD)?,
+            tdx_quote: bytes_field(TDX_QUOTE_FIELD)?,
+        })
+    }
+}
+
+/// Reads an unsigned integer from a Move value rendered as JSON.
+fn json_u64(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(number) => number.as_u64(),
+        serde_json::Value::String(string) => string.parse().ok(),
+        serde_json::Value::Object(object) => object
+            .get("value")
+            .or_else(|| object.get("fields"))
+            .and_then(json_u64),
+        _ => None,
+    }
+}
+
+/// Reads a byte vector from a Move value rendered as JSON.
+fn json_bytes(value: &serde_json::Value) -> Option<Vec<u8>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+        .collect()
+}
+
+/// A transaction to the Secret Guessing module, built but not yet signed nor sent.
+#[derive(Clone, Debug)]
+pub struct PreparedTransaction {
+    /// The unsigned transaction
+    tx: TransactionData,
+
+    /// The name of the Move function called, for error reporting
+    function: &'static str,
+
+    /// Whether the calls of the transaction were already dry-run successfully
+    simulated: bool,
+}
+
+impl PreparedTransaction {
+    /// Returns the digest the transaction has once executed.
+    pub fn digest(&self) -> TransactionDigest {
+        self.tx.digest()
+    }
+}
+
+/// The outcome of an executed transaction.
+#[derive(Clone, Debug)]
+pub struct TxOutcome {
+    /// The transaction digest
+    pub digest: String,
+
+    /// The execution status
+    pub status: SuiExecutionStatus,
+
+    /// The gas the transaction was charged
+    pub gas_used: GasCostSummary,
+
+    /// The balance changes of every address touched by the transaction
+    pub balance_changes: Vec<BalanceChange>,
+
+    /// The objects created by the transaction
+    pub created: Vec<OwnedObjectRef>,
+
+    /// The objects mutated by the transaction
+    pub mutated: Vec<OwnedObjectRef>,
+
+    /// The events emitted by the transaction
+    pub events: Vec<SuiEvent>,
+}
+
+impl TxOutcome {
+    /// Returns the net amount of SUI (in MIST) an address received in the transaction,
+    /// negative if the address paid more than it received.
+    pub fn sui_received_by(&self, address: SuiAddress) -> i128 {
+        let sui = GAS::type_tag();
+        self.balance_changes
+            .iter()
+            .filter(|change| {
+                change.owner == Owner::AddressOwner(address) && change.coin_type == sui
+            })
+            .map(|change| change.amount)
+            .sum()
+    }
+
+    /// Returns the net gas cost of the transaction (in MIST), after the storage rebate.
+    pub fn net_gas_cost(&self) -> i64 {
+        self.gas_used.net_gas_usage()
+    }
+}
+
+impl From<SuiTransactionBlockResponse> for TxOutcome {
+    fn from(response: SuiTransactionBlockResponse) -> Self {
+        let effects = response.effects.as_ref();
+        Self {
+            digest: response.digest.to_string(),
+            status: effects.map_or(SuiExecutionStatus::Success, |effects| {
+                effects.status().clone()
+            }),
+            gas_used: effects
+                .map(|effects| effects.gas_cost_summary().clone())
+                .unwrap_or_default(),
+            balance_changes: response.balance_changes.unwrap_or_default(),
+            created: effects
+                .map(|effects| effects.created().to_vec())
+                .unwrap_or_default(),
+            mutated: effects
+                .map(|effects| effects.mutated().to_vec())
+                .unwrap_or_default(),
+            events: response
+                .events
+                .map(|events| events.data)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Why a transaction failed, in simulation or on-chain.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ExecutionFailure {
+    /// A Move function aborted. The abort code is reported as is, to be looked up in
+    /// the source of the aborting module
+    #[error(
+        "Move abort in `{}::{}` with code {code}",
+        module.as_deref().unwrap_or("unknown"),
+        function.as_deref().unwrap_or("unknown")
+    )]
+    MoveAbort {
+        /// The name of the module that aborted, if known
+        module: Option<String>,
+
+        /// The name of the function that aborted, if known
+        function: Option<String>,
+
+        /// The abort code
+        code: u64,
+    },
+
+    /// Any other execution failure, e.g. running out of gas
+    #[error("{0}")]
+    Other(String),
+}
+
+impl ExecutionFailure {
+    /// Parses the execution error reported in transaction effects.
+    ///
+    /// Move aborts are reported as
+    /// `MoveAbort(MoveLocation { module: ModuleId { address: .., name: Identifier("..") }, .., function_name: Some("..") }, <code>) in command <n>`.
+    pub fn parse(error: &str) -> Self {
+        let Some(location) = error.strip_prefix("MoveAbort(") else {
+            return Self::Other(error.to_string());
+        };
+        let code = location
+            .rfind("}, ")
+            .map(|start| &location[start + 3..])
+            .and_then(|rest| rest.split(')').next())
+            .and_then(|code| code.trim().parse::<u64>().ok());
+        let Some(code) = code else {
+            return Self::Other(error.to_string());
+        };
+        let module = location
+            .split_once("name: Identifier(\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(module, _)| module.to_string());
+        let function = location
+            .split_once("function_name: Some(\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(function, _)| function.to_string());
+        Self::MoveAbort {
+            module,
+            function,
+            code,
+        }
     }
 }
 
@@ -184,8 +1270,171 @@ impl SuiClientContext {
 pub enum SuiClientError {
     #[error("Failed to get active address")]
     GetActiveAddressError(#[from] SuiError),
-    #[error("Failed to parse object ID")]
-    ParseObjectIDError(#[from] ObjectIDParseError),
     #[error("Failed to withdraw funds from treasury pool")]
-    WithdrawFundsFromTreasuryPoolError(#[from] anyhow::Error),
+    WithdrawFundsFromTreasuryPoolError(#[source] anyhow::Error),
+    #[error("Failed to access the wallet: {0}")]
+    WalletError(#[source] anyhow::Error),
+    #[error("Failed to build the transaction: {0}")]
+    BuildTransactionError(#[source] anyhow::Error),
+    #[error("Failed to query the Sui RPC node: {0}")]
+    RpcError(#[from] sui_sdk::error::Error),
+    #[error("`{function}` failed in simulation: {failure}")]
+    DryRunFailed {
+        function: &'static str,
+        failure: ExecutionFailure,
+    },
+    #[error("`{function}` failed on-chain, tx_hash: {tx_hash}: {failure}")]
+    TransactionFailed {
+        function: &'static str,
+        tx_hash: String,
+        failure: ExecutionFailure,
+    },
+    #[error("Wallet balance of {balance} MIST is below the halt threshold of {threshold} MIST")]
+    LowGasBalance { balance: u64, threshold: u64 },
+    #[error("No gas coin can pay a gas budget of {budget} MIST")]
+    NoGasCoin { budget: u64 },
+    #[error("Gas coin {0} is not owned by the wallet")]
+    GasCoinNotFound(ObjectID),
+    #[error("Object {0} is not a shared object")]
+    NotSharedObject(ObjectID),
+    #[error("Object {0} has no Move content")]
+    MissingObjectContent(ObjectID),
+    #[error("The Secret Guessing database has no valid `{0}` field")]
+    InvalidGameState(&'static str),
+    #[error("Address {0} owns no Secret Guessing admin capability")]
+    MissingAdminCap(SuiAddress),
+}
+
+impl SuiClientError {
+    /// Returns the Move abort the transaction failed with, if any.
+    pub fn move_abort(&self) -> Option<&ExecutionFailure> {
+        match self {
+            Self::DryRunFailed { failure, .. } | Self::TransactionFailed { failure, .. } => {
+                matches!(failure, ExecutionFailure::MoveAbort { .. }).then_some(failure)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_move_abort_reports_location_and_code() {
+        let error = "MoveAbort(MoveLocation { module: ModuleId { address: 0000000000000000000000000000000000000000000000000000000000000abc, name: Identifier(\"secret_guessing\") }, function: 7, instruction: 21, function_name: Some(\"withdraw_funds_from_treasury_pool\") }, 1) in command 0";
+        assert_eq!(
+            ExecutionFailure::parse(error),
+            ExecutionFailure::MoveAbort {
+                module: Some("secret_guessing".to_string()),
+                function: Some("withdraw_funds_from_treasury_pool".to_string()),
+                code: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_move_abort_without_function_name() {
+        let error = "MoveAbort(MoveLocation { module: ModuleId { address: 0000000000000000000000000000000000000000000000000000000000000002, name: Identifier(\"balance\") }, function: 3, instruction: 10, function_name: None }, 2) in command 1";
+        let failure = ExecutionFailure::parse(error);
+        assert_eq!(
+            failure,
+            ExecutionFailure::MoveAbort {
+                module: Some("balance".to_string()),
+                function: None,
+                code: 2,
+            }
+        );
+        assert_eq!(
+            failure.to_string(),
+            "Move abort in `balance::unknown` with code 2"
+        );
+    }
+
+    #[test]
+    fn game_state_from_fields_decodes_rendered_move_values() {
+        let fields = serde_json::json!({
+            "treasury_pool": { "type": "0x2::balance::Balance<0x2::sui::SUI>", "fields": { "value": "5000000000" } },
+            "fee": "100000000",
+            "guess_count": 42,
+            "epoch": "7",
+            "public_key_bytes": [1, 2, 3],
+            "tdx_quote_v4": [],
+        });
+        assert_eq!(
+            GameState::from_fields(&fields).unwrap(),
+            GameState {
+                treasury_pool_balance: 5_000_000_000,
+                fee: 100_000_000,
+                guess_count: 42,
+                epoch: 7,
+                public_key: vec![1, 2, 3],
+                tdx_quote: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn game_state_from_fields_accepts_a_flattened_balance() {
+        let fields = serde_json::json!({
+            "treasury_pool": { "value": "12" },
+            "fee": "1",
+            "guess_count": "0",
+            "epoch": "0",
+            "public_key_bytes": [],
+            "tdx_quote_v4": [],
+        });
+        assert_eq!(
+            GameState::from_fields(&fields)
+                .unwrap()
+                .treasury_pool_balance,
+            12
+        );
+    }
+
+    #[test]
+    fn game_state_from_fields_names_the_invalid_field() {
+        let valid = serde_json::json!({
+            "treasury_pool": "1",
+            "fee": "1",
+            "guess_count": "1",
+            "epoch": "1",
+            "public_key_bytes": [],
+            "tdx_quote_v4": [],
+        });
+        for (field, value) in [
+            (FEE_FIELD, serde_json::json!(null)),
+            (EPOCH_FIELD, serde_json::json!("not a number")),
+            (PUBLIC_KEY_FIELD, serde_json::json!([1, 256])),
+            (TDX_QUOTE_FIELD, serde_json::json!("0x00")),
+        ] {
+            let mut fields = valid.clone();
+            fields[field] = value;
+            assert!(matches!(
+                GameState::from_fields(&fields),
+                Err(SuiClientError::InvalidGameState(name)) if name == field
+            ));
+        }
+        let mut fields = valid;
+        fields.as_object_mut().unwrap().remove(GUESS_COUNT_FIELD);
+        assert!(matches!(
+            GameState::from_fields(&fields),
+            Err(SuiClientError::InvalidGameState(GUESS_COUNT_FIELD))
+        ));
+    }
+
+    #[test]
+    fn parse_other_failures_verbatim() {
+        for error in [
+            "InsufficientGas",
+            "MoveAbort(garbled",
+            "MoveAbort(MoveLocation { module: ModuleId { name: Identifier(\"m\") } }, not_a_code)",
+        ] {
+            assert_eq!(
+                ExecutionFailure::parse(error),
+                ExecutionFailure::Other(error.to_string())
+            );
+        }
+    }
 }
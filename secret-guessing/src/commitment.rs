@@ -0,0 +1,41 @@
+use crate::atoma::utils::blake2b_hash;
+
+/// The size of the salt of a secret commitment in bytes
+pub const COMMITMENT_SALT_SIZE: usize = 32;
+
+/// The size of a secret commitment in bytes
+pub const COMMITMENT_SIZE: usize = 32;
+
+/// Computes the commitment to a secret, published on-chain when a round starts.
+///
+/// The commitment is the Blake2b-256 hash of the salt followed by the UTF-8 bytes of
+/// the secret, which a Move contract can recompute with `sui::hash::blake2b256`.
+///
+/// # Arguments
+///
+/// * `secret` - The secret
+/// * `salt` - The salt, kept private until the secret is revealed
+pub fn compute_commitment(secret: &str, salt: &[u8]) -> [u8; COMMITMENT_SIZE] {
+    let mut preimage = Vec::with_capacity(salt.len() + secret.len());
+    preimage.extend_from_slice(salt);
+    preimage.extend_from_slice(secret.as_bytes());
+    blake2b_hash(&preimage).into()
+}
+
+/// Verifies a revealed secret and salt against the commitment published on-chain.
+///
+/// Anyone can run this check once a round's secret is revealed, to make sure the
+/// secret players were guessing is the one committed to when the round started.
+///
+/// # Arguments
+///
+/// * `commitment` - The commitment, as published on-chain
+/// * `secret` - The revealed secret
+/// * `salt` - The revealed salt
+///
+/// # Returns
+///
+/// `true` if the reveal matches the commitment.
+pub fn verify_reveal(commitment: &[u8], secret: &str, salt: &[u8]) -> bool {
+    compute_commitment(secret, salt).as_slice() == commitment
+}
@@ -1,20 +1,55 @@
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+use thiserror::Error;
 
 /// Configuration for the Secret Guessing application
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SecretGuessingConfig {
+    /// Channels on which winners and hints are announced
+    pub announcer: Option<AnnouncerConfig>,
+
     /// API key for Atoma service authentication
     pub atoma_api_key: String,
 
+    /// File path of the append-only judging audit log
+    pub audit_log_path: Option<String>,
+
     /// File path for storing cursor information
     pub cursor_path: String,
 
+    /// Base delay in seconds for the dead-letter retry backoff
+    pub dead_letter_backoff_secs: Option<u64>,
+
+    /// Maximum number of automatic retries for a dead-lettered event
+    pub dead_letter_max_attempts: Option<u32>,
+
+    /// File path for storing events that failed to be handled
+    pub dead_letter_path: Option<String>,
+
+    /// Management of the gas coins paying for the agent's transactions
+    pub gas: Option<GasConfig>,
+
     /// The number of consecutive guesses to wait before providing a new hint
     pub hint_wait_count: u64,
 
+    /// Checks run on generated hints, to make sure they do not give the secret away
+    pub hint_leakage: Option<HintLeakageConfig>,
+
     /// HTTP address of the RPC node
     pub http_rpc_node_addr: String,
 
+    /// Multi-judge quorum that must agree before a guess triggers a payout
+    pub judging_quorum: Option<JudgingQuorumConfig>,
+
+    /// The most revealing hint level, hints never give the answer away even at this level
+    pub max_hint_level: Option<u32>,
+
+    /// Maximum length of a guess in characters, longer guesses are rejected without being judged
+    pub max_guess_length: Option<usize>,
+
+    /// Maximum number of guesses judged concurrently by the Atoma service
+    pub max_concurrent_judgements: Option<usize>,
+
     /// The model to use for the Atoma service
     pub model: String,
 
@@ -24,6 +59,443 @@ pub struct SecretGuessingConfig {
     /// Package identifier for the smart contract
     pub package_id: String,
 
+    /// Operator approval of the payouts of high-value pools, disabled if not set
+    pub payout_approval: Option<PayoutApprovalConfig>,
+
+    /// File path of the write-ahead journal of the payouts to winners
+    pub payout_journal_path: Option<String>,
+
+    /// How the treasury pool is paid out to winners, the full pool to a single winner by default
+    pub payout_policy: Option<PayoutPolicyConfig>,
+
     /// Optional timeout duration for requests in seconds
     pub request_timeout: Option<u64>,
+
+    /// Whether to resubmit the attestation, with a fresh TDX quote, when the public key
+    /// registered on-chain at startup is not the sealed round's one. The agent halts on
+    /// such a mismatch if not set
+    pub resubmit_attestation: Option<bool>,
+
+    /// Sealing of the current round's state to disk, so that restarts resume the same round
+    pub sealing: Option<SealingConfig>,
+
+    /// Themes, language and difficulty of the secrets
+    pub secret_category: Option<SecretCategoryConfig>,
+
+    /// How the secret of each round is picked, defaults to the model
+    pub secret_source: Option<SecretSourceConfig>,
+
+    /// Validation of the secrets picked by the model
+    pub secret_validation: Option<SecretValidationConfig>,
+
+    /// Where to start reading events from, when no cursor has been saved yet
+    pub startup_policy: Option<StartupPolicy>,
+
+    /// Synonym table for the local guess matcher, mapping a word to the words
+    /// that should be accepted in its place
+    pub synonyms: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Configuration of the announcement channels.
+///
+/// Each configured channel receives every announcement. If no channel is configured,
+/// announcements are appended to a local JSON lines file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AnnouncerConfig {
+    /// Authoring of the posts by the model, the plain templates are posted if unset
+    pub authoring: Option<PostAuthoringConfig>,
+
+    /// Discord webhook channel
+    pub discord: Option<DiscordAnnouncerConfig>,
+
+    /// File path of a local JSON lines announcement log
+    pub jsonl_path: Option<String>,
+
+    /// Maximum number of attempts to deliver an announcement to a channel
+    pub max_attempts: Option<u32>,
+
+    /// Base delay in seconds for the announcement retry backoff
+    pub retry_backoff_secs: Option<u64>,
+
+    /// Telegram bot channel
+    pub telegram: Option<TelegramAnnouncerConfig>,
+
+    /// X (formerly Twitter) channel
+    pub x: Option<XAnnouncerConfig>,
+}
+
+/// Configuration of the operator approval of high-value payouts.
+///
+/// The payout of a correct guess whose treasury pool balance exceeds the threshold is
+/// queued, and only sent once an operator approves it, through the CLI or the local
+/// HTTP endpoint. The round stays open meanwhile: the later correct guesses of the round
+/// queue behind it, and one is only paid if every earlier one is rejected.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PayoutApprovalConfig {
+    /// Address of the local HTTP endpoint through which payouts are approved or
+    /// rejected (e.g. `127.0.0.1:8090`). The endpoint is not served if not set
+    pub http_addr: Option<String>,
+
+    /// Bearer token the requests to the HTTP endpoint must carry in their
+    /// `Authorization` header. Required to serve the endpoint
+    pub http_token: Option<String>,
+
+    /// File path of the queue of payouts waiting for approval
+    pub queue_path: Option<String>,
+
+    /// Treasury pool balance in MIST above which a payout waits for approval
+    pub threshold: u64,
+
+    /// What happens to a payout left undecided for `timeout_secs`, rejected by default
+    pub timeout_action: Option<ApprovalTimeoutAction>,
+
+    /// Seconds after which an undecided payout is decided by `timeout_action`. Payouts
+    /// wait for an operator indefinitely if not set
+    pub timeout_secs: Option<u64>,
+}
+
+/// The decision taken on a payout left undecided past the approval timeout.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalTimeoutAction {
+    /// Send the payout
+    Approve,
+
+    /// Drop the payout, the guess is then treated as an incorrect one
+    #[default]
+    Reject,
+}
+
+/// Configuration of how the treasury pool is paid out to winners.
+///
+/// The policy in effect when a round starts applies to the whole round, so that a change
+/// only takes effect from the next rotation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PayoutPolicyConfig {
+    /// Address receiving the house fee, required if the house fee is not zero
+    pub house_address: Option<String>,
+
+    /// Share of each payout kept as a house fee, in basis points
+    pub house_fee_bps: Option<u32>,
+
+    /// Share of the treasury pool paid out on a win, in basis points, the rest rolling
+    /// over to the next round. Defaults to the full pool
+    pub payout_bps: Option<u32>,
+
+    /// Whether the correct guesses of the same checkpoint split the payout, instead of
+    /// the first one winning it all
+    pub split_same_checkpoint: Option<bool>,
+}
+
+/// Configuration of the gas coins paying for the agent's transactions.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GasConfig {
+    /// Safety margin added to the gas estimated from a dry-run, as a fraction (e.g. `0.2` for 20%)
+    pub budget_margin: Option<f64>,
+
+    /// Wallet balance in MIST below which no transaction is sent anymore
+    pub halt_balance: Option<u64>,
+
+    /// Highest gas budget of a transaction in MIST
+    pub max_budget: Option<u64>,
+
+    /// Lowest gas budget of a transaction in MIST
+    pub min_budget: Option<u64>,
+
+    /// Number of gas coins the wallet balance is split into, each holding a maximum gas
+    /// budget, so that a transaction rarely needs its coins merged first
+    pub pool_size: Option<usize>,
+
+    /// Wallet balance in MIST below which a warning is logged before each transaction
+    pub warn_balance: Option<u64>,
+}
+
+/// Configuration of the sealed persistence of the current round's state.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SealingConfig {
+    /// Allows the `file` key provider, which must never be used in production
+    pub dev: Option<bool>,
+
+    /// The source of the sealing key, which must be configured
+    pub key_provider: Option<KeyProviderConfig>,
+
+    /// File path of the sealed state
+    pub path: Option<String>,
+}
+
+/// Configuration of the category secrets are picked from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SecretCategoryConfig {
+    /// The difficulty of the secrets, left to the model if unset
+    pub difficulty: Option<Difficulty>,
+
+    /// The language of the secrets and hints, defaults to English
+    pub language: Option<String>,
+
+    /// Themes to pick the secrets from, one per round in a rotating schedule
+    /// (e.g. `["history", "science", "mythology"]`). Any noun can be picked if unset
+    pub themes: Option<Vec<String>>,
+}
+
+impl SecretCategoryConfig {
+    /// Returns the category of the given round, whose theme is the next one in the schedule.
+    pub fn category_for_round(&self, round: u64) -> SecretCategory {
+        let theme = self
+            .themes
+            .as_ref()
+            .filter(|themes| !themes.is_empty())
+            .map(|themes| themes[(round % themes.len() as u64) as usize].clone());
+        SecretCategory {
+            difficulty: self.difficulty,
+            language: self
+                .language
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string()),
+            theme,
+        }
+    }
+}
+
+/// The language of the secrets and hints, if none is configured
+pub const DEFAULT_LANGUAGE: &str = "English";
+
+/// The category of a round's secret, fixed for the whole round.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SecretCategory {
+    /// The difficulty of the secret, left to the model if unset
+    pub difficulty: Option<Difficulty>,
+
+    /// The language of the secret and hints
+    pub language: String,
+
+    /// The theme of the secret, if any
+    pub theme: Option<String>,
+}
+
+impl Default for SecretCategory {
+    fn default() -> Self {
+        Self {
+            difficulty: None,
+            language: DEFAULT_LANGUAGE.to_string(),
+            theme: None,
+        }
+    }
+}
+
+impl SecretCategory {
+    /// Whether the secret is an English word.
+    pub fn is_english(&self) -> bool {
+        self.language.eq_ignore_ascii_case(DEFAULT_LANGUAGE)
+            || self.language.eq_ignore_ascii_case("en")
+    }
+}
+
+/// How hard a secret is to guess.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    /// An everyday word
+    Easy,
+
+    /// A word most players know, but would not think of right away
+    Medium,
+
+    /// A rare or specialized word
+    Hard,
+}
+
+/// How the secret of each round is picked.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretSourceConfig {
+    /// The model picks the secret, which is then validated
+    Model,
+
+    /// The secret is derived from the on-chain random seed and a committed word list, so
+    /// that its selection can be audited. The model is then only used for hints and judging,
+    /// and the themes and difficulty of the secret category do not apply
+    WordList {
+        /// File path of the word list, in the format of the bundled `data/nouns.txt`.
+        /// Defaults to the bundled word list
+        word_list_path: Option<String>,
+    },
+}
+
+/// Configuration of the validation of the secrets picked by the model.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SecretValidationConfig {
+    /// Additional words that are never accepted as secrets
+    pub blocklist: Option<Vec<String>>,
+
+    /// File path of the history of past secrets
+    pub history_path: Option<String>,
+
+    /// Maximum number of times the model is asked for a secret, per round
+    pub max_attempts: Option<u32>,
+
+    /// Number of days before a past secret can be picked again, never if unset
+    pub repeat_window_days: Option<u64>,
+
+    /// File path of an additional word list, in the format of the bundled `data/nouns.txt`
+    pub word_list_path: Option<String>,
+}
+
+/// The source of the key sealing the round's state.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeyProviderConfig {
+    /// A key derived inside an Intel TDX trust domain, bound to its measurements
+    Tdx {
+        /// File path of the root key released to the trust domain at boot
+        root_key_path: String,
+    },
+
+    /// A key read from a local file, for development only (requires `sealing.dev`)
+    File {
+        /// File path of the sealing key, created if it does not exist
+        key_path: String,
+    },
+}
+
+/// Configuration of the model authoring the announcement posts.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PostAuthoringConfig {
+    /// Additional terms that posts must not contain, on top of the default blocklist
+    pub blocked_terms: Option<Vec<String>>,
+
+    /// Maximum length of a post in characters, on top of each channel's own limit
+    pub max_length: Option<usize>,
+
+    /// The model authoring the posts, defaults to the main model
+    pub model: Option<String>,
+
+    /// The persona the model writes as, e.g. "a witty pirate running a treasure hunt"
+    pub persona: Option<String>,
+}
+
+/// Configuration of the Discord announcement channel.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DiscordAnnouncerConfig {
+    /// The Discord webhook URL
+    pub webhook_url: String,
+
+    /// Minimum interval in seconds between two messages
+    pub min_interval_secs: Option<u64>,
+}
+
+/// Configuration of the Telegram announcement channel.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TelegramAnnouncerConfig {
+    /// The Telegram bot token
+    pub bot_token: String,
+
+    /// The identifier of the chat (or `@channelusername`) to post to
+    pub chat_id: String,
+
+    /// Minimum interval in seconds between two messages
+    pub min_interval_secs: Option<u64>,
+}
+
+/// Configuration of the X announcement channel.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct XAnnouncerConfig {
+    /// OAuth 2.0 user access token, with the `tweet.write` scope
+    pub access_token: String,
+
+    /// Minimum interval in seconds between two posts
+    pub min_interval_secs: Option<u64>,
+}
+
+/// Configuration of the filter that rejects hints leaking the secret.
+///
+/// Leaking hints are regenerated up to `max_retries` times, after which no hint is
+/// published for that round.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HintLeakageConfig {
+    /// Embedding model used to compare the meaning of a hint to the secret, the
+    /// embedding similarity check is skipped if unset
+    pub embedding_model: Option<String>,
+
+    /// Maximum edit distance at or below which a word of the hint is a variant of the secret
+    pub max_edit_distance: Option<usize>,
+
+    /// Maximum cosine similarity allowed between the embeddings of a hint and of the secret
+    pub max_embedding_similarity: Option<f32>,
+
+    /// Maximum number of times a leaking hint is regenerated
+    pub max_retries: Option<u32>,
+}
+
+/// Configuration of the multi-judge quorum deciding whether a guess is correct.
+///
+/// Each judge is queried with a separate inference request, and each choice it returns
+/// counts as one vote. A payout only goes through once `threshold` votes deem the guess
+/// correct, while split decisions are escalated to the review queue.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JudgingQuorumConfig {
+    /// The judges of the quorum
+    pub judges: Vec<JudgeConfig>,
+
+    /// Minimum number of correct votes needed for a payout, between 1 and the total
+    /// number of choices of the judges
+    pub threshold: usize,
+
+    /// File path of the review queue for split decisions
+    pub review_queue_path: Option<String>,
+}
+
+/// Configuration of a single judge of the quorum
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct JudgeConfig {
+    /// The model used by the judge, defaults to the application model
+    pub model: Option<String>,
+
+    /// The seed of the judge's inference requests, defaults to the game's random seed
+    /// offset by the judge's position in the quorum
+    pub seed: Option<u64>,
+
+    /// Number of choices requested from the model (`n`), each counting as one vote
+    pub choices: Option<u32>,
+}
+
+/// Where the engine starts reading events from, when no cursor has been saved yet.
+///
+/// Whatever the policy, events older than the current secret's epoch are only
+/// observed by the engine, and never trigger payouts.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPolicy {
+    /// Start right after the latest event emitted by the module
+    #[default]
+    FromNow,
+
+    /// Start right after the latest `RotateTdxQuoteEvent` emitted by the module
+    FromLastRotation,
+
+    /// Replay the module's whole event history, in observe-only mode
+    ObserveReplay,
+}
+
+impl SecretGuessingConfig {
+    /// Loads the configuration from a TOML file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the TOML configuration file
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` if the file cannot be read or is not a valid configuration.
+    pub fn from_file_path<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read configuration file: {0}")]
+    ReadConfigFileError(#[from] std::io::Error),
+    #[error("Failed to parse configuration file: {0}")]
+    ParseConfigFileError(#[from] toml::de::Error),
 }
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sui_sdk::types::{base_types::SuiAddress, event::EventID};
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::SecretGuessingConfig,
+    store::{self, JsonStore, StoreError},
+};
+
+/// The default file path for the dead-letter store
+const DEFAULT_DEAD_LETTER_PATH: &str = "dead_letter.json";
+
+/// The default maximum number of automatic retries for a dead-lettered event
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// The default base delay for the retry backoff, in seconds
+const DEFAULT_BACKOFF_BASE_SECS: u64 = 30;
+
+/// The maximum delay between two retries of the same event, in seconds
+const MAX_BACKOFF_SECS: u64 = 60 * 60;
+
+type Result<T> = std::result::Result<T, DeadLetterError>;
+
+/// An event that could not be handled by the engine.
+///
+/// The raw event payload is kept as emitted by the chain, so that a retry goes
+/// through the exact same parsing path as the original attempt.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeadLetterEntry {
+    /// The identifier of the entry, in the form `<tx_digest>:<event_seq>`
+    pub id: String,
+
+    /// The on-chain identifier of the event
+    pub event_id: EventID,
+
+    /// The Move event name, e.g. `NewGuessEvent`
+    pub event_name: String,
+
+    /// The sender of the transaction that emitted the event
+    pub sender: SuiAddress,
+
+    /// The on-chain timestamp of the event, in milliseconds
+    #[serde(default)]
+    pub timestamp_ms: Option<u64>,
+
+    /// The raw JSON payload of the event
+    pub payload: Value,
+
+    /// The error returned by the last attempt to handle the event
+    pub error: String,
+
+    /// The number of attempts made to handle the event so far
+    pub attempts: u32,
+
+    /// Unix timestamp (in seconds) of the first failure
+    pub first_failed_at: u64,
+
+    /// Unix timestamp (in seconds) of the most recent failure
+    pub last_failed_at: u64,
+
+    /// Unix timestamp (in seconds) after which the event is retried, or `None`
+    /// if automatic retries are exhausted and the event awaits an operator
+    pub next_retry_at: Option<u64>,
+
+    /// Whether the event blocks the events that follow it, in which case the engine
+    /// retries it in place, by reading the events again from right before it, instead
+    /// of retrying it out of order
+    #[serde(default)]
+    pub blocking: bool,
+}
+
+impl std::fmt::Display for DeadLetterEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let next_retry = self
+            .next_retry_at
+            .map_or_else(|| "parked".to_string(), |at| at.to_string());
+        write!(
+            f,
+            "{} event={} sender={} attempts={} last_failed_at={} next_retry_at={} blocking={} error={}",
+            self.id,
+            self.event_name,
+            self.sender,
+            self.attempts,
+            self.last_failed_at,
+            next_retry,
+            self.blocking,
+            self.error
+        )
+    }
+}
+
+/// An event read from the chain, as recorded when it fails to be handled.
+#[derive(Clone, Debug)]
+pub struct FailedEvent {
+    /// The on-chain identifier of the event
+    pub event_id: EventID,
+
+    /// The Move event name, e.g. `NewGuessEvent`
+    pub event_name: String,
+
+    /// The sender of the transaction that emitted the event
+    pub sender: SuiAddress,
+
+    /// The on-chain timestamp of the event, in milliseconds
+    pub timestamp_ms: Option<u64>,
+
+    /// The raw JSON payload of the event
+    pub payload: Value,
+}
+
+impl From<DeadLetterEntry> for FailedEvent {
+    fn from(entry: DeadLetterEntry) -> Self {
+        Self {
+            event_id: entry.event_id,
+            event_name: entry.event_name,
+            sender: entry.sender,
+            timestamp_ms: entry.timestamp_ms,
+            payload: entry.payload,
+        }
+    }
+}
+
+/// A persistent store for events that failed to be handled, shared by the running engine
+/// and the CLI.
+pub struct DeadLetterStore {
+    /// The entries, keyed by their identifier
+    entries: JsonStore<DeadLetterEntry>,
+
+    /// Maximum number of automatic retries for an event
+    max_attempts: u32,
+
+    /// Base delay for the exponential retry backoff, in seconds
+    backoff_base_secs: u64,
+}
+
+impl DeadLetterStore {
+    /// Constructor
+    pub fn new(path: String, max_attempts: u32, backoff_base_secs: u64) -> Self {
+        Self {
+            entries: JsonStore::new(path),
+            max_attempts,
+            backoff_base_secs,
+        }
+    }
+
+    /// Creates a dead-letter store from the application configuration, falling
+    /// back to the default values for any unset option.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        Self::new(
+            config
+                .dead_letter_path
+                .clone()
+                .unwrap_or_else(|| DEFAULT_DEAD_LETTER_PATH.to_string()),
+            config
+                .dead_letter_max_attempts
+                .unwrap_or(DEFAULT_MAX_ATTEMPTS),
+            config
+                .dead_letter_backoff_secs
+                .unwrap_or(DEFAULT_BACKOFF_BASE_SECS),
+        )
+    }
+
+    /// Returns all the dead-lettered events, ordered by their identifier.
+    pub fn list(&self) -> Result<Vec<DeadLetterEntry>> {
+        Ok(self.entries.values()?)
+    }
+
+    /// Returns the dead-lettered events whose next retry is due at `now`, leaving out the
+    /// blocking events, which are retried in place.
+    pub fn due(&self, now: u64) -> Result<Vec<DeadLetterEntry>> {
+        Ok(self
+            .entries
+            .values()?
+            .into_iter()
+            .filter(|entry| !entry.blocking && entry.next_retry_at.is_some_and(|at| at <= now))
+            .collect())
+    }
+
+    /// Records a failed attempt to handle an event.
+    ///
+    /// The first failure of an event inserts a new entry; subsequent failures bump its
+    /// attempt count and push the next retry further out, doubling the delay each time.
+    /// Once `max_attempts` is reached, the entry is parked until an operator retries it.
+    ///
+    /// A blocking event holds back the events that follow it until it is handled or
+    /// parked, and is retried in place by the engine rather than from the store.
+    #[instrument(level = "info", skip_all, fields(event_name = %event.event_name))]
+    pub fn record_failure(
+        &self,
+        event: FailedEvent,
+        error: String,
+        blocking: bool,
+    ) -> Result<DeadLetterEntry> {
+        let now = store::unix_timestamp_secs();
+        let id = entry_id(&event.event_id);
+        let entry = self.entries.update(|entries| {
+            let entry = entries
+                .entry(id.clone())
+                .and_modify(|entry| {
+                    entry.attempts += 1;
+                    entry.error = error.clone();
+                    entry.last_failed_at = now;
+                    entry.blocking = blocking;
+                })
+                .or_insert_with(|| DeadLetterEntry {
+                    id,
+                    event_id: event.event_id,
+                    event_name: event.event_name,
+                    sender: event.sender,
+                    timestamp_ms: event.timestamp_ms,
+                    payload: event.payload,
+                    error,
+                    attempts: 1,
+                    first_failed_at: now,
+                    last_failed_at: now,
+                    next_retry_at: None,
+                    blocking,
+                });
+            entry.next_retry_at = if entry.attempts < self.max_attempts {
+                Some(now + self.backoff_delay_secs(entry.attempts))
+            } else {
+                None
+            };
+            Ok::<_, DeadLetterError>(entry.clone())
+        })?;
+
+        match entry.next_retry_at {
+            Some(next_retry_at) => info!(
+                target = "dead-letter",
+                id = %entry.id,
+                attempts = entry.attempts,
+                "Event dead-lettered, next retry at: {next_retry_at}"
+            ),
+            None => warn!(
+                target = "dead-letter",
+                id = %entry.id,
+                attempts = entry.attempts,
+                "Event exhausted its automatic retries, waiting for an operator"
+            ),
+        }
+        Ok(entry)
+    }
+
+    /// Schedules an event for an immediate retry by the running engine, resetting its
+    /// attempt count. A parked blocking event no longer blocks anything, as the engine
+    /// has moved past it, so it is then retried from the store. Returns `false` if no
+    /// entry with the given identifier exists.
+    pub fn schedule_retry(&self, id: &str) -> Result<bool> {
+        self.entries.update(|entries| {
+            let Some(entry) = entries.get_mut(id) else {
+                return Ok(false);
+            };
+            if entry.next_retry_at.is_none() {
+                entry.blocking = false;
+            }
+            entry.attempts = 0;
+            entry.next_retry_at = Some(0);
+            Ok(true)
+        })
+    }
+
+    /// Removes an event from the store, either because it was handled successfully
+    /// or because an operator discarded it.
+    pub fn remove(&self, id: &str) -> Result<Option<DeadLetterEntry>> {
+        Ok(self.entries.remove(id)?)
+    }
+
+    /// Computes the delay before the next retry, after `attempts` failed attempts.
+    fn backoff_delay_secs(&self, attempts: u32) -> u64 {
+        let exponent = attempts.saturating_sub(1).min(32);
+        self.backoff_base_secs
+            .saturating_mul(1u64 << exponent)
+            .min(MAX_BACKOFF_SECS)
+    }
+}
+
+/// Builds the dead-letter identifier of an event, in the form `<tx_digest>:<event_seq>`.
+pub fn entry_id(event_id: &EventID) -> String {
+    format!("{}:{}", event_id.tx_digest, event_id.event_seq)
+}
+
+#[derive(Debug, Error)]
+pub enum DeadLetterError {
+    #[error("Dead-letter store error: {0}")]
+    StoreError(#[from] StoreError),
+}
@@ -0,0 +1,98 @@
+use std::str::FromStr;
+
+use tracing::{error, info, instrument, warn};
+
+use super::{
+    events::{self, SecretGuessingEventIdentifier},
+    GuessAiEngine, SuiEventSubscriberError,
+};
+use crate::dead_letter::{DeadLetterEntry, FailedEvent};
+
+impl GuessAiEngine {
+    /// Records an event that failed to be handled in the dead-letter store.
+    ///
+    /// Failing to persist the event is logged but not propagated, as there is nothing
+    /// else the engine can do about it besides continuing to process new events.
+    ///
+    /// # Returns
+    ///
+    /// The dead-letter entry of the event, or `None` if it failed to be persisted.
+    pub(super) fn dead_letter_event(
+        &self,
+        event: FailedEvent,
+        error: &SuiEventSubscriberError,
+        blocking: bool,
+    ) -> Option<DeadLetterEntry> {
+        match self
+            .dead_letter
+            .record_failure(event, error.to_string(), blocking)
+        {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                error!(
+                    target = "atoma-sui-subscriber",
+                    event = "subscriber-dead-letter-error",
+                    "Failed to dead-letter event: {e}"
+                );
+                None
+            }
+        }
+    }
+
+    /// Retries all the dead-lettered events whose backoff has elapsed.
+    ///
+    /// Events that are handled successfully are removed from the dead-letter store,
+    /// while events that fail again are rescheduled with a longer backoff.
+    #[instrument(level = "info", skip_all)]
+    pub(super) async fn retry_dead_letters(&mut self) {
+        let due = match self.dead_letter.due(crate::store::unix_timestamp_secs()) {
+            Ok(due) => due,
+            Err(e) => {
+                error!(
+                    target = "atoma-sui-subscriber",
+                    event = "subscriber-dead-letter-error",
+                    "Failed to read dead-lettered events: {e}"
+                );
+                return;
+            }
+        };
+        for entry in due {
+            info!(
+                target = "atoma-sui-subscriber",
+                event = "subscriber-dead-letter-retry",
+                id = %entry.id,
+                attempts = entry.attempts,
+                "Retrying dead-lettered event"
+            );
+            let result = match SecretGuessingEventIdentifier::from_str(&entry.event_name).and_then(
+                |event_identifier| events::parse_event(event_identifier, entry.payload.clone()),
+            ) {
+                Ok(event) => {
+                    self.handle_event(event, entry.event_id, entry.sender, entry.timestamp_ms)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    if let Err(e) = self.dead_letter.remove(&entry.id) {
+                        error!(
+                            target = "atoma-sui-subscriber",
+                            event = "subscriber-dead-letter-error",
+                            "Failed to remove dead-lettered event: {e}"
+                        );
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        target = "atoma-sui-subscriber",
+                        event = "subscriber-dead-letter-retry-error",
+                        id = %entry.id,
+                        "Failed to handle dead-lettered event: {e}"
+                    );
+                    self.dead_letter_event(FailedEvent::from(entry), &e, false);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde_json::json;
+use tracing::{instrument, warn};
+
+use super::{
+    prompts::{self, HintPromptResponse},
+    GuessAiEngine, Result,
+};
+use crate::leakage::DEFAULT_MAX_HINT_RETRIES;
+
+/// The default most revealing hint level
+const DEFAULT_MAX_HINT_LEVEL: u32 = 5;
+
+impl GuessAiEngine {
+    /// Computes how revealing the next hint should be, given the current guess count.
+    ///
+    /// The level grows by one every `hint_wait_count` guesses, starting at 1, and is
+    /// capped at the configured maximum hint level.
+    pub(super) fn hint_level(&self, guess_count: u64) -> u32 {
+        let max_hint_level = self
+            .config
+            .max_hint_level
+            .unwrap_or(DEFAULT_MAX_HINT_LEVEL)
+            .max(1);
+        let level = guess_count / self.config.hint_wait_count.max(1);
+        level.clamp(1, u64::from(max_hint_level)) as u32
+    }
+
+    /// Generates a new hint about the current secret, that does not leak the secret.
+    ///
+    /// Each generated hint goes through the hint leakage filter. Leaking hints are
+    /// regenerated, with a different seed, up to the configured number of retries.
+    ///
+    /// # Returns
+    ///
+    /// The hint, or `None` if every attempt leaked the secret, in which case no hint
+    /// should be published for this round.
+    #[instrument(level = "info", skip_all, fields(level = level))]
+    pub(super) async fn generate_safe_hint(&self, level: u32) -> Result<Option<String>> {
+        let max_retries = self
+            .config
+            .hint_leakage
+            .as_ref()
+            .and_then(|leakage| leakage.max_retries)
+            .unwrap_or(DEFAULT_MAX_HINT_RETRIES);
+        for attempt in 0..=max_retries {
+            let hint = self
+                .generate_hint(level, self.random_seed.wrapping_add(u64::from(attempt)))
+                .await?;
+            match self
+                .hint_filter
+                .check(&self.client_private_key, &hint, &self.secret)
+                .await?
+            {
+                None => return Ok(Some(hint)),
+                Some(reason) => {
+                    warn!(
+                        target = "sui_event_subscriber",
+                        event = "new-guess-event",
+                        attempt = attempt,
+                        "Rejected a generated hint that leaks the secret: {reason}"
+                    );
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Asks the model for a new hint about the current secret.
+    ///
+    /// The model is given the hints it already gave for the current secret, so that
+    /// each new hint adds information instead of repeating a previous one.
+    #[instrument(level = "info", skip_all, fields(level = level))]
+    async fn generate_hint(&self, level: u32, seed: u64) -> Result<String> {
+        let max_hint_level = self
+            .config
+            .max_hint_level
+            .unwrap_or(DEFAULT_MAX_HINT_LEVEL)
+            .max(1);
+        let hint_prompt = prompts::create_hint_prompt(
+            &self.secret,
+            &self.category,
+            level,
+            max_hint_level,
+            &self.hints,
+        );
+        let response_body = self
+            .atoma_sdk
+            .confidential_chat_completions(
+                &self.client_private_key,
+                serde_json::from_value(json!({
+                    "model": self.config.model.clone(),
+                    "messages": [
+                        { "role": "system", "content": hint_prompt },
+                    ],
+                    "seed": seed,
+                }))?,
+            )
+            .await?;
+
+        let HintPromptResponse { hint } = serde_json::from_str::<HintPromptResponse>(
+            &response_body.choices[0].message.content.clone(),
+        )?;
+        Ok(hint)
+    }
+}
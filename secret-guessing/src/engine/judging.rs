@@ -0,0 +1,283 @@
+use futures::future;
+use rand::Rng;
+use serde_json::json;
+use tracing::{error, info, instrument, warn};
+
+use super::{
+    prompts::{self, GuessPromptResponse},
+    GuessAiEngine, GuessVerdict, JudgeLayer, Result,
+};
+use crate::{
+    atoma::SignedResponse,
+    audit::JudgingRecord,
+    config::JudgeConfig,
+    injection,
+    matcher::MatchOutcome,
+    quorum::{JudgeVote, QuorumDecision},
+    review::ReviewResolution,
+};
+
+/// The interval between two checks of the review queue for resolved guesses
+const REVIEW_CHECK_INTERVAL_SECS: u64 = 5;
+
+impl GuessAiEngine {
+    /// Judges whether a guess matches the current secret.
+    ///
+    /// The guess is first checked by the local `GuessMatcher`, which settles exact
+    /// matches and obvious misses without any Atoma round-trip. Only the guesses the
+    /// matcher is not confident about are sent to the judging quorum.
+    ///
+    /// This method has no side effects, which allows multiple guesses to be judged
+    /// concurrently against the same secret.
+    #[instrument(level = "info", skip_all, fields(guess = %guess))]
+    pub(super) async fn judge_guess(&self, guess: &str) -> Result<GuessVerdict> {
+        let max_guess_length = self
+            .config
+            .max_guess_length
+            .unwrap_or(injection::DEFAULT_MAX_GUESS_LENGTH);
+        if let Some(reason) = injection::screen_guess(guess, max_guess_length) {
+            warn!(
+                target = "sui_event_subscriber",
+                event = "new-guess-event",
+                "Guess rejected by the injection screen: {reason}"
+            );
+            return Ok(GuessVerdict::new(false, reason, JudgeLayer::Screen));
+        }
+
+        let verdict = match self.matcher.check(guess, &self.secret) {
+            MatchOutcome::Correct(explanation) => {
+                GuessVerdict::new(true, explanation, JudgeLayer::Matcher)
+            }
+            MatchOutcome::Incorrect(explanation) => {
+                GuessVerdict::new(false, explanation, JudgeLayer::Matcher)
+            }
+            MatchOutcome::Ambiguous => self.ask_judges(guess).await?,
+        };
+        info!(
+            target = "sui_event_subscriber",
+            event = "new-guess-event",
+            decided_by = %verdict.decided_by,
+            is_correct = verdict.is_correct,
+            "Judged guess: {}",
+            verdict.explanation
+        );
+        Ok(verdict)
+    }
+
+    /// Asks the judging quorum whether a guess matches the current secret.
+    ///
+    /// Every judge is queried concurrently, and each of the choices it returns counts as
+    /// one vote. A correct vote only counts if the judge's extracted guess matches the
+    /// submitted guess. The guess is deemed correct only if the quorum threshold is met;
+    /// split decisions are deemed incorrect and flagged for escalation to the review queue.
+    #[instrument(level = "info", skip_all, fields(guess = %guess))]
+    async fn ask_judges(&self, guess: &str) -> Result<GuessVerdict> {
+        let votes = future::try_join_all(
+            self.quorum
+                .judges
+                .iter()
+                .enumerate()
+                .map(|(index, judge)| self.ask_judge(guess, index, judge)),
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        let num_correct = votes.iter().filter(|vote| vote.is_correct).count();
+        let num_rejected = votes.iter().filter(|vote| !vote.verified).count();
+        let summary = format!(
+            "{num_correct} of {} votes deemed the guess correct (threshold: {})",
+            votes.len(),
+            self.quorum.threshold
+        );
+        let mut verdict = match self.quorum.tally(&votes) {
+            QuorumDecision::Correct => GuessVerdict::new(true, summary, JudgeLayer::Model),
+            QuorumDecision::Incorrect if num_rejected > 0 => {
+                // NOTE: A correct verdict on anything other than the submitted guess must
+                // never reach the treasury, so these votes were downgraded to incorrect ones
+                GuessVerdict::new(
+                    false,
+                    format!("{summary}, {num_rejected} correct votes failed verification"),
+                    JudgeLayer::Verification,
+                )
+            }
+            QuorumDecision::Incorrect => GuessVerdict::new(false, summary, JudgeLayer::Model),
+            QuorumDecision::Split => {
+                let mut verdict = GuessVerdict::new(false, summary, JudgeLayer::Model);
+                verdict.escalated = true;
+                verdict
+            }
+        };
+        verdict.votes = votes;
+        Ok(verdict)
+    }
+
+    /// Asks a single judge whether a guess matches the current secret, returning
+    /// one vote per choice in the judge's response.
+    async fn ask_judge(
+        &self,
+        guess: &str,
+        index: usize,
+        judge: &JudgeConfig,
+    ) -> Result<Vec<JudgeVote>> {
+        let model = judge
+            .model
+            .clone()
+            .unwrap_or_else(|| self.config.model.clone());
+        let seed = judge
+            .seed
+            .unwrap_or_else(|| self.random_seed.wrapping_add(index as u64));
+        let boundary = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        let (system_prompt, user_prompt) =
+            prompts::check_guess_prompt(guess, &self.secret, &self.category, &boundary);
+        let SignedResponse {
+            body: response_body,
+            signature,
+            response_hash,
+        } = self
+            .atoma_sdk
+            .confidential_chat_completions_signed(
+                &self.client_private_key,
+                serde_json::from_value(json!({
+                    "model": model,
+                    "messages": [
+                        {"role": "system", "content": system_prompt},
+                        {"role": "user", "content": user_prompt},
+                    ],
+                    "seed": seed,
+                    "n": judge.choices.unwrap_or(1).max(1),
+                }))?,
+            )
+            .await?;
+
+        response_body
+            .choices
+            .iter()
+            .map(|choice| -> Result<JudgeVote> {
+                let GuessPromptResponse {
+                    is_correct,
+                    explanation,
+                    extracted_guess,
+                } = serde_json::from_str::<GuessPromptResponse>(&choice.message.content)?;
+                let verified = !is_correct
+                    || injection::verify_extracted_guess(guess, extracted_guess.as_deref());
+                if !verified {
+                    warn!(
+                        target = "sui_event_subscriber",
+                        event = "new-guess-event",
+                        model = %model,
+                        extracted_guess = ?extracted_guess,
+                        "Model-extracted guess does not match the submitted guess, rejecting correct vote"
+                    );
+                }
+                Ok(JudgeVote {
+                    model: model.clone(),
+                    seed,
+                    is_correct: is_correct && verified,
+                    verified,
+                    explanation,
+                    signature: signature.clone(),
+                    response_hash: response_hash.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Settles the guesses an operator resolved in the review queue, at most once every
+    /// `REVIEW_CHECK_INTERVAL_SECS`.
+    ///
+    /// A guess resolved as correct is awarded the round like a guess the judges agreed
+    /// on, unless its round is over, in which case it must be paid by hand. A guess
+    /// resolved as incorrect is remembered by the matcher. A resolved guess whose
+    /// settlement fails stays in the queue, and is retried on the next check.
+    #[instrument(level = "info", skip_all)]
+    pub(super) async fn process_reviews(&mut self) {
+        let now = crate::store::unix_timestamp_secs();
+        if now < self.reviews_checked_at + REVIEW_CHECK_INTERVAL_SECS {
+            return;
+        }
+        self.reviews_checked_at = now;
+
+        let resolved = match self.review_queue.resolved() {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                error!(
+                    target = "sui_event_subscriber",
+                    "Failed to read the review queue: {e}"
+                );
+                return;
+            }
+        };
+        for entry in resolved {
+            let is_correct = entry.resolution == Some(ReviewResolution::Correct);
+            if let Err(e) = self.audit_log.append(&JudgingRecord {
+                event_id: entry.event_id,
+                sender: entry.sender,
+                guess: entry.guess.clone(),
+                is_correct,
+                escalated: false,
+                decided_by: JudgeLayer::Operator.to_string(),
+                explanation: "Resolved by an operator after the judges were split".to_string(),
+                votes: entry.votes.clone(),
+                judged_at: now,
+            }) {
+                error!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    "Failed to append the review resolution to the audit log: {e}"
+                );
+            }
+            if !is_correct {
+                info!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    "Guess was resolved as incorrect by an operator"
+                );
+                if entry.round == self.round {
+                    self.matcher.record_miss(&entry.guess);
+                }
+            } else if entry.round != self.round || self.is_round_closed() {
+                error!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    sender = %entry.sender,
+                    round = entry.round,
+                    "Guess was resolved as correct after the end of its round, it must be paid by hand"
+                );
+            } else {
+                info!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    "Guess was resolved as correct by an operator, awarding the round"
+                );
+                let verdict = GuessVerdict {
+                    is_correct: true,
+                    explanation: "Resolved as correct by an operator after the judges were split"
+                        .to_string(),
+                    decided_by: JudgeLayer::Operator,
+                    votes: entry.votes.clone(),
+                    escalated: false,
+                };
+                if let Err(e) = self
+                    .award_correct_guess(&entry.submitted(), vec![], verdict)
+                    .await
+                {
+                    error!(
+                        target = "sui_event_subscriber",
+                        id = %entry.id,
+                        "Failed to settle the guess resolved as correct, retrying on the next check: {e}"
+                    );
+                    continue;
+                }
+            }
+            if let Err(e) = self.review_queue.remove(&entry.id) {
+                error!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    "Failed to remove a settled guess from the review queue: {e}"
+                );
+            }
+        }
+    }
+}
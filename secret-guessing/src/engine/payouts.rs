@@ -0,0 +1,507 @@
+use sui_sdk::types::base_types::SuiAddress;
+use tracing::{error, info, instrument, warn};
+
+use super::{events::SubmittedGuess, GuessAiEngine, GuessVerdict, Result};
+use crate::{
+    announcer::Announcement,
+    approval::{self, ApprovalEntry, ApprovalQueue, Settlement},
+    client::TxOutcome,
+    payout::{PayoutEntry, PayoutPlan, PayoutStatus},
+};
+
+/// The interval between two checks of the payout approval queue for decided payouts
+const APPROVAL_CHECK_INTERVAL_SECS: u64 = 5;
+
+impl GuessAiEngine {
+    /// Pays the winner of a guess, along with its co-winners, exactly once.
+    ///
+    /// A payout approved by an operator sends exactly the amounts of its plan, computed
+    /// when it was queued. Otherwise, a single winner of a full pool policy is paid the
+    /// whole pool, and other payouts are computed by the round's payout policy from the
+    /// current balance of the pool, and paid out of it, the rest rolling over to the next
+    /// round.
+    ///
+    /// The digest of the payout transaction is journaled before it is sent. If a previous
+    /// attempt may have been sent (e.g. the agent crashed while it was in flight, and the
+    /// guess is replayed), its digest is first looked up on-chain, and the payout is not
+    /// sent again if it was executed.
+    ///
+    /// # Returns
+    ///
+    /// The outcome of the payout transaction, or `None` if the winner was already paid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal cannot be updated or the payout fails, in which
+    /// case the guess is dead-lettered and the payout retried later.
+    #[instrument(level = "info", skip_all, fields(winner = %winner.sender))]
+    async fn pay_winner(
+        &mut self,
+        winner: &SubmittedGuess,
+        co_winners: &[(SuiAddress, String)],
+        approved_plan: Option<&PayoutPlan>,
+    ) -> Result<Option<TxOutcome>> {
+        let entry = self.payout_journal.get_or_insert(
+            winner.event_id,
+            winner.sender,
+            co_winners.iter().map(|(co_winner, _)| *co_winner).collect(),
+            winner.guess.clone(),
+            winner.treasury_pool_balance,
+        )?;
+        if let PayoutStatus::Paid { tx_hash, .. } = &entry.status {
+            info!(
+                target = "sui_event_subscriber",
+                event = "new-guess-event",
+                "Winner was already paid for this guess, tx_hash: {tx_hash}"
+            );
+            return Ok(None);
+        }
+        if self.confirm_payout(&entry).await? {
+            return Ok(None);
+        }
+
+        // The winners are the journaled ones, so that a retry pays the same winners
+        let winners = std::iter::once(entry.winner)
+            .chain(entry.co_winners.iter().copied())
+            .collect::<Vec<_>>();
+        let plan = match approved_plan {
+            Some(plan) => Some(plan.clone()),
+            None if self.payout_policy.is_full_pool() && winners.len() == 1 => None,
+            None => Some(self.plan_payout(&winners).await?),
+        };
+        let prepared = match &plan {
+            None => {
+                self.sui_client_ctx
+                    .prepare_withdraw_funds_from_treasury_pool(winner.sender, None, None, None)
+                    .await
+            }
+            Some(plan) => {
+                self.sui_client_ctx
+                    .prepare_withdraw_payouts(&plan.recipients, None, None, None)
+                    .await
+            }
+        };
+        let result = match prepared {
+            Ok(prepared) => {
+                self.payout_journal
+                    .record_attempt(&winner.event_id, prepared.digest())?;
+                self.sui_client_ctx.execute_prepared(prepared).await
+            }
+            Err(e) => Err(e),
+        };
+        let outcome = match result {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                // The guess is dead-lettered, so that the payout is retried once the
+                // cause of the failure (e.g. a decoded Move abort) is dealt with
+                error!(
+                    target = "sui_event_subscriber",
+                    event = "new-guess-event",
+                    move_abort = ?e.move_abort(),
+                    "Failed to withdraw funds from treasury pool for the winner: {e}"
+                );
+                return Err(e.into());
+            }
+        };
+        self.payout_journal.record_paid(
+            &winner.event_id,
+            outcome.digest.clone(),
+            outcome.sui_received_by(winner.sender),
+        )?;
+        Ok(Some(outcome))
+    }
+
+    /// Computes the amounts paid to the given winners by the payout policy, from the
+    /// current balance of the treasury pool.
+    async fn plan_payout(&self, winners: &[SuiAddress]) -> Result<PayoutPlan> {
+        let pool_balance = self
+            .sui_client_ctx
+            .fetch_game_state()
+            .await?
+            .treasury_pool_balance;
+        let plan = self.payout_policy.plan(pool_balance, winners);
+        info!(
+            target = "sui_event_subscriber",
+            event = "new-guess-event",
+            pool_balance,
+            num_winners = winners.len(),
+            house_fee = plan.house_fee,
+            rollover = plan.rollover,
+            "Paying out the treasury pool according to the payout policy"
+        );
+        Ok(plan)
+    }
+
+    /// Looks up on-chain the journaled payout transactions of an entry, and records the
+    /// entry as paid if one of them was executed successfully.
+    ///
+    /// # Returns
+    ///
+    /// Whether the winner was paid.
+    async fn confirm_payout(&self, entry: &PayoutEntry) -> Result<bool> {
+        let Some(outcome) = self
+            .sui_client_ctx
+            .executed_transactions(&entry.tx_digests)
+            .await?
+            .into_iter()
+            .find(|outcome| outcome.status.is_ok())
+        else {
+            return Ok(false);
+        };
+        info!(
+            target = "sui_event_subscriber",
+            id = %entry.id,
+            "Found on-chain the payout of a journaled winner, tx_hash: {}",
+            outcome.digest
+        );
+        self.payout_journal.record_paid(
+            &entry.event_id,
+            outcome.digest.clone(),
+            outcome.sui_received_by(entry.winner),
+        )?;
+        Ok(true)
+    }
+
+    /// Reconciles the journaled payouts whose transaction may have been sent before a
+    /// restart with their on-chain effects, at startup.
+    ///
+    /// The payouts that are not found on-chain are left pending, to be sent when their
+    /// guess is replayed or retried.
+    #[instrument(level = "info", skip_all)]
+    pub(super) async fn reconcile_payouts(&self) -> Result<()> {
+        for entry in self.payout_journal.unconfirmed()? {
+            if !self.confirm_payout(&entry).await? {
+                warn!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    attempts = entry.attempts,
+                    "Journaled payout was not found on-chain, it is sent again when its guess is handled"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Awards the current round to a correct guess, along with its co-winners.
+    ///
+    /// If the payout requires an operator's approval, its amounts are computed and it is
+    /// queued, and the round stays open: the later guesses are still judged, and a later
+    /// correct guess queues behind it, in case it is rejected. Otherwise, the winners are
+    /// paid and the secret is revealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payout cannot be computed, queued or sent.
+    pub(super) async fn award_correct_guess(
+        &mut self,
+        submitted: &SubmittedGuess,
+        co_winners: Vec<(SuiAddress, String)>,
+        verdict: GuessVerdict,
+    ) -> Result<()> {
+        if !self
+            .requires_approval(submitted.treasury_pool_balance)
+            .await?
+        {
+            return self.settle_winning_guess(submitted, co_winners, None).await;
+        }
+        let winners = std::iter::once(submitted.sender)
+            .chain(co_winners.iter().map(|(co_winner, _)| *co_winner))
+            .collect::<Vec<_>>();
+        let plan = self.plan_payout(&winners).await?;
+        let entry = ApprovalEntry::new(
+            submitted,
+            co_winners,
+            plan,
+            self.round,
+            verdict.decided_by.to_string(),
+            verdict.explanation,
+            verdict.votes,
+        );
+        let (entry, queued) = self
+            .approval_queue
+            .blocking(move |queue| queue.enqueue(entry))
+            .await?;
+        if queued {
+            warn!(
+                target = "sui_event_subscriber",
+                event = "new-guess-event",
+                id = %entry.id,
+                "The payout waits for an operator, the round stays open until it is approved"
+            );
+        } else {
+            info!(
+                target = "sui_event_subscriber",
+                event = "new-guess-event",
+                id = %entry.id,
+                status = ?entry.status,
+                "The payout of the guess was already queued for approval"
+            );
+        }
+        Ok(())
+    }
+
+    /// Pays the winner of a correct guess, along with its co-winners, announces them
+    /// and reveals the secret, ending the round. An approved payout sends exactly the
+    /// amounts of its plan.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payout fails or the won round cannot be sealed, in which
+    /// case the secret is not revealed.
+    #[instrument(level = "info", skip_all, fields(winner = %winner.sender))]
+    async fn settle_winning_guess(
+        &mut self,
+        winner: &SubmittedGuess,
+        co_winners: Vec<(SuiAddress, String)>,
+        approved_plan: Option<&PayoutPlan>,
+    ) -> Result<()> {
+        // `None` if the winner was paid before a restart, so only the reveal may be left
+        let outcome = self.pay_winner(winner, &co_winners, approved_plan).await?;
+        // The round is closed before anything else, so that no later guess is paid out
+        // even if the secret then fails to be revealed
+        self.winning_event = Some(winner.event_id);
+        self.seal_state()?;
+        if let Some(outcome) = outcome {
+            self.announce_payout(winner, co_winners, &outcome);
+        }
+        self.reveal_secret().await;
+        Ok(())
+    }
+
+    /// Logs and announces the payout of a winner, along with its co-winners.
+    fn announce_payout(
+        &self,
+        winner: &SubmittedGuess,
+        co_winners: Vec<(SuiAddress, String)>,
+        outcome: &TxOutcome,
+    ) {
+        let (sender, treasury_pool_balance) = (winner.sender, winner.treasury_pool_balance);
+        // The balance changes of the payout are the source of truth for the amount
+        // the winner was paid, as the pool may have changed since the guess was made
+        let amount_paid = outcome.sui_received_by(sender);
+        info!(
+            target = "sui_event_subscriber",
+            event = "new-guess-event",
+            amount_paid = %amount_paid,
+            gas_cost = outcome.net_gas_cost(),
+            "Withdrew funds from treasury pool successfully, tx_hash: {}",
+            outcome.digest
+        );
+        if self.payout_policy.is_full_pool()
+            && co_winners.is_empty()
+            && amount_paid != i128::from(treasury_pool_balance)
+        {
+            warn!(
+                target = "sui_event_subscriber",
+                event = "new-guess-event",
+                "Winner was paid {amount_paid} MIST, but the guess event reported a treasury pool balance of {treasury_pool_balance} MIST, tx_hash: {}",
+                outcome.digest
+            );
+        }
+        self.announcer.announce(Announcement::Winner {
+            sender,
+            guess: winner.guess.clone(),
+            treasury_pool_balance: u64::try_from(amount_paid)
+                .ok()
+                .filter(|amount_paid| *amount_paid > 0)
+                .unwrap_or(treasury_pool_balance),
+            tx_hash: outcome.digest.clone(),
+        });
+        for (co_winner, co_winner_guess) in co_winners {
+            let amount_paid = outcome.sui_received_by(co_winner);
+            info!(
+                target = "sui_event_subscriber",
+                event = "new-guess-event",
+                amount_paid = %amount_paid,
+                "Co-winner {co_winner} shares the payout, guess: {co_winner_guess}"
+            );
+            self.announcer.announce(Announcement::Winner {
+                sender: co_winner,
+                guess: co_winner_guess,
+                treasury_pool_balance: u64::try_from(amount_paid).unwrap_or_default(),
+                tx_hash: outcome.digest.clone(),
+            });
+        }
+    }
+
+    /// Whether the payout of a correct guess reporting the given treasury pool balance
+    /// must wait for an operator's approval: the balance is above the approval threshold,
+    /// or the payout of an earlier guess of the round waits, and this one queues behind it.
+    async fn requires_approval(&self, treasury_pool_balance: u64) -> Result<bool> {
+        let Some(approval) = &self.config.payout_approval else {
+            return Ok(false);
+        };
+        if treasury_pool_balance > approval.threshold {
+            return Ok(true);
+        }
+        let round = self.round;
+        Ok(self
+            .approval_queue
+            .blocking(move |queue| queue.has_unsettled(round))
+            .await?)
+    }
+
+    /// Acts on the operators' decisions on the queued payouts, at most once every
+    /// `APPROVAL_CHECK_INTERVAL_SECS`.
+    ///
+    /// Payouts left undecided past the approval timeout are first decided by the
+    /// configured timeout action. The payouts are then settled in the order their guesses
+    /// were made, as decided by [`approval::settlements`]: the first approved payout of the
+    /// current round wins it like any other correct guess, revealing the secret, and one
+    /// whose round rotated while it waited is paid without closing the current round.
+    /// Rejected payouts are treated as incorrect guesses. A payout that fails to be sent
+    /// stays approved, and is retried on the next check.
+    #[instrument(level = "info", skip_all)]
+    pub(super) async fn process_approvals(&mut self) {
+        let Some(approval) = self.config.payout_approval.clone() else {
+            return;
+        };
+        let now = crate::store::unix_timestamp_secs();
+        if now < self.approvals_checked_at + APPROVAL_CHECK_INTERVAL_SECS {
+            return;
+        }
+        self.approvals_checked_at = now;
+
+        if let Some(timeout_secs) = approval.timeout_secs {
+            let timeout_action = approval.timeout_action.unwrap_or_default();
+            match self
+                .approval_queue
+                .blocking(move |queue| queue.expire(now, timeout_secs, timeout_action))
+                .await
+            {
+                Ok(expired) => {
+                    for entry in expired {
+                        warn!(
+                            target = "sui_event_subscriber",
+                            id = %entry.id,
+                            status = ?entry.status,
+                            "Payout was not decided by an operator within {timeout_secs} seconds"
+                        );
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        target = "sui_event_subscriber",
+                        "Failed to expire undecided payouts: {e}"
+                    );
+                }
+            }
+        }
+
+        let entries = match self.approval_queue.blocking(ApprovalQueue::list).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!(
+                    target = "sui_event_subscriber",
+                    "Failed to read the payout approval queue: {e}"
+                );
+                return;
+            }
+        };
+        for (entry, settlement) in approval::settlements(entries) {
+            let superseded = match settlement {
+                Settlement::Reject => {
+                    info!(
+                        target = "sui_event_subscriber",
+                        id = %entry.id,
+                        "Payout was rejected, the guess is treated as an incorrect one"
+                    );
+                    if entry.round == self.round {
+                        self.matcher.record_miss(&entry.guess);
+                    }
+                    false
+                }
+                Settlement::Supersede => {
+                    info!(
+                        target = "sui_event_subscriber",
+                        id = %entry.id,
+                        "An earlier guess won the round of the payout, it is not sent"
+                    );
+                    true
+                }
+                Settlement::Pay if entry.round != self.round => {
+                    // The guess was made before the rotation revealed the secret of its
+                    // round, so it is paid, but it does not win the current round
+                    warn!(
+                        target = "sui_event_subscriber",
+                        id = %entry.id,
+                        round = entry.round,
+                        "Payout was approved after its round rotated, paying the winner"
+                    );
+                    let winner = entry.submitted();
+                    match self
+                        .pay_winner(&winner, &entry.co_winners, Some(&entry.plan))
+                        .await
+                    {
+                        Ok(Some(outcome)) => {
+                            self.announce_payout(&winner, entry.co_winners.clone(), &outcome)
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            error!(
+                                target = "sui_event_subscriber",
+                                id = %entry.id,
+                                "Failed to pay out approved payout, retrying on the next check: {e}"
+                            );
+                            continue;
+                        }
+                    }
+                    false
+                }
+                Settlement::Pay
+                    if self.is_round_closed() && self.winning_event != Some(entry.event_id) =>
+                {
+                    warn!(
+                        target = "sui_event_subscriber",
+                        id = %entry.id,
+                        winning_event = ?self.winning_event,
+                        "Payout was approved, but the round is already over, it is not sent"
+                    );
+                    true
+                }
+                Settlement::Pay => {
+                    info!(
+                        target = "sui_event_subscriber",
+                        id = %entry.id,
+                        decided_by = ?entry.decided_by,
+                        "Payout was approved, paying the winner"
+                    );
+                    if let Err(e) = self
+                        .settle_winning_guess(
+                            &entry.submitted(),
+                            entry.co_winners.clone(),
+                            Some(&entry.plan),
+                        )
+                        .await
+                    {
+                        error!(
+                            target = "sui_event_subscriber",
+                            id = %entry.id,
+                            "Failed to pay out approved payout, retrying on the next check: {e}"
+                        );
+                        continue;
+                    }
+                    false
+                }
+            };
+            let id = entry.id.clone();
+            let settled = self
+                .approval_queue
+                .blocking(move |queue| {
+                    if superseded {
+                        queue.supersede(&id)
+                    } else {
+                        queue.settle(&id)
+                    }
+                })
+                .await;
+            if let Err(e) = settled {
+                error!(
+                    target = "sui_event_subscriber",
+                    id = %entry.id,
+                    "Failed to record the settlement of a decided payout: {e}"
+                );
+            }
+        }
+    }
+}
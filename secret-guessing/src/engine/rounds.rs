@@ -0,0 +1,315 @@
+use std::time::Duration;
+
+use fastcrypto::encoding::{Encoding, Hex};
+use tracing::{error, info, instrument, warn};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use super::{
+    events::RotateTdxQuoteEvent, prompts, unix_timestamp_millis, GuessAiEngine, Result,
+    SuiEventSubscriberError,
+};
+use crate::{
+    config::{SecretCategory, SecretGuessingConfig, SecretSourceConfig},
+    generate_secret::{generate_new_secret, GeneratedSecret, SecretRequest},
+    payout::PayoutPolicy,
+    sealing::{self, SealedState},
+    selection::SaltChain,
+};
+
+/// The interval between two attempts at revealing the secret of a won round
+const REVEAL_RETRY_INTERVAL_SECS: u64 = 30;
+
+/// The number of attempts at reading the on-chain game state at startup
+const GAME_STATE_FETCH_ATTEMPTS: u32 = 5;
+
+/// The delay before the first retry of reading the on-chain game state, doubled on each retry
+const GAME_STATE_FETCH_BACKOFF_SECS: u64 = 2;
+
+impl GuessAiEngine {
+    /// Reconciles the local state with the on-chain state of the game, at startup.
+    ///
+    /// The state is logged, so that operators can check that the treasury and the guess
+    /// count match what the agent last observed. Reading the state is retried with an
+    /// exponential backoff, as the agent must not run with an unchecked attestation.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt if the state cannot be read within
+    /// `GAME_STATE_FETCH_ATTEMPTS` attempts.
+    /// Returns `SuiEventSubscriberError::PublicKeyMismatch` if the public key registered
+    /// on-chain is not the one of the sealed round (e.g. another agent instance attested
+    /// while this one was down), so that the agent halts for an operator to investigate.
+    /// If `resubmit_attestation` is set, the attestation is resubmitted with a fresh TDX
+    /// quote instead, and an error is only returned if that fails.
+    #[instrument(level = "info", skip_all)]
+    pub(super) async fn reconcile_game_state(&mut self) -> Result<()> {
+        let mut attempt = 1;
+        let game_state = loop {
+            match self.sui_client_ctx.fetch_game_state().await {
+                Ok(game_state) => break game_state,
+                Err(e) if attempt < GAME_STATE_FETCH_ATTEMPTS => {
+                    let delay_secs = GAME_STATE_FETCH_BACKOFF_SECS << (attempt - 1);
+                    warn!(
+                        target = "sui_event_subscriber",
+                        attempt,
+                        "Failed to read the on-chain game state at startup, retrying in {delay_secs}s: {e}"
+                    );
+                    tokio::time::sleep(Duration::from_secs(delay_secs)).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    error!(
+                        target = "sui_event_subscriber",
+                        "Failed to read the on-chain game state at startup, halting: {e}"
+                    );
+                    return Err(e.into());
+                }
+            }
+        };
+        info!(
+            target = "sui_event_subscriber",
+            treasury_pool_balance = game_state.treasury_pool_balance,
+            fee = game_state.fee,
+            guess_count = game_state.guess_count,
+            epoch = game_state.epoch,
+            "Read the on-chain game state"
+        );
+
+        let public_key = PublicKey::from(&self.client_private_key);
+        if game_state.public_key.as_slice() == public_key.as_bytes() {
+            return Ok(());
+        }
+        let registered_public_key = Hex::encode(&game_state.public_key);
+        if !self.config.resubmit_attestation.unwrap_or_default() {
+            error!(
+                target = "sui_event_subscriber",
+                registered_public_key = %registered_public_key,
+                sealed_public_key = %Hex::encode(public_key.as_bytes()),
+                "The public key registered on-chain is not the one of the sealed round, halting"
+            );
+            return Err(SuiEventSubscriberError::PublicKeyMismatch(
+                registered_public_key,
+            ));
+        }
+        warn!(
+            target = "sui_event_subscriber",
+            registered_public_key = %registered_public_key,
+            "The public key registered on-chain is not the one of the sealed round, resubmitting the attestation"
+        );
+        // The quote binds the public key, so that the attestation vouches for it
+        let tdx_quote_bytes = sealing::attestation_quote(&self.config, public_key.as_bytes())?;
+        self.sui_client_ctx
+            .submit_node_public_key(public_key, tdx_quote_bytes, None, None, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether the current round is over for the players, i.e. it was won or its secret
+    /// was revealed, so that no further guess can be paid out until the next rotation.
+    pub(super) fn is_round_closed(&self) -> bool {
+        self.winning_event.is_some() || self.secret_revealed
+    }
+
+    /// Retries revealing the secret of a won round, at most once every
+    /// `REVEAL_RETRY_INTERVAL_SECS`, until it succeeds.
+    ///
+    /// The secret stays unrevealed until the payout of the winning guess is journaled as
+    /// paid.
+    pub(super) async fn retry_reveal(&mut self) {
+        let Some(winning_event) = self.winning_event else {
+            return;
+        };
+        if self.secret_revealed {
+            return;
+        }
+        let now = crate::store::unix_timestamp_secs();
+        if now < self.reveal_attempted_at + REVEAL_RETRY_INTERVAL_SECS {
+            return;
+        }
+        match self.payout_journal.is_paid(&winning_event) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                // The journal is checked again on the next retry
+                self.reveal_attempted_at = now;
+                error!(
+                    target = "sui_event_subscriber",
+                    "Failed to read the payout journal before revealing the secret: {e}"
+                );
+                return;
+            }
+        }
+        warn!(
+            target = "sui_event_subscriber",
+            "The round was won but its secret is not revealed yet, retrying the reveal"
+        );
+        self.reveal_secret().await;
+    }
+
+    #[instrument(level = "info", skip_all, fields(event = "rotate-tdx-quote-event"))]
+    pub(super) async fn handle_rotate_tdx_quote_event(
+        &mut self,
+        event: RotateTdxQuoteEvent,
+        timestamp_ms: Option<u64>,
+    ) -> Result<()> {
+        let RotateTdxQuoteEvent { epoch, random_seed } = event;
+        info!(
+            target = "sui_event_subscriber",
+            event = "rotate-tdx-quote-event",
+            "RotateTdxQuoteEvent for epoch: {epoch}"
+        );
+        if self.is_stale(timestamp_ms) {
+            info!(
+                target = "sui_event_subscriber",
+                event = "rotate-tdx-quote-event",
+                "RotateTdxQuoteEvent for epoch: {epoch} predates the current secret, skipping rotation"
+            );
+            return Ok(());
+        }
+        // Reveal the previous secret, unless it was already revealed when it was guessed
+        self.reveal_secret().await;
+        if let Err(e) = self.secret_validator.record(&self.secret) {
+            error!(
+                target = "sui_event_subscriber",
+                event = "rotate-tdx-quote-event",
+                "Failed to record the previous secret in the secret history: {e}"
+            );
+        }
+        let round = self.round + 1;
+        let category = Self::category_for_round(&self.config, round);
+        let payout_policy = PayoutPolicy::from_config(&self.config)?;
+        let mut rng = rand::thread_rng();
+        let client_private_key = StaticSecret::random_from_rng(&mut rng);
+        // The salt was committed to by the previous round's salt, before the random seed
+        // was emitted, unless the chain is exhausted
+        let salt_chain = match self.salt_chain.as_ref().and_then(SaltChain::next) {
+            Some(salt_chain) => salt_chain,
+            None => {
+                warn!(
+                    target = "sui_event_subscriber",
+                    event = "rotate-tdx-quote-event",
+                    "Starting a new salt chain, this round's salt is not committed to by the previous reveal"
+                );
+                SaltChain::generate()
+            }
+        };
+        let salt = salt_chain.salt();
+        let request = SecretRequest {
+            prompt: prompts::create_secret_prompt(&category),
+            random_seed,
+            salt,
+            category: &category,
+        };
+        let GeneratedSecret { secret, .. } = generate_new_secret(
+            &self.atoma_sdk,
+            &client_private_key,
+            request,
+            &self.config,
+            self.secret_selector.as_ref(),
+            &self.secret_validator,
+            &mut self.sui_client_ctx,
+        )
+        .await?;
+        // Update the self's state
+        self.client_private_key = client_private_key;
+        self.random_seed = random_seed;
+        self.secret = secret;
+        self.secret_salt = salt;
+        self.salt_chain = Some(salt_chain);
+        self.secret_revealed = false;
+        self.winning_event = None;
+        self.round = round;
+        self.category = category;
+        self.payout_policy = payout_policy;
+        self.hints.clear();
+        self.matcher.reset_misses();
+        // Guesses emitted on-chain after the rotation belong to the new secret's epoch
+        self.secret_epoch_start_ms = timestamp_ms.unwrap_or_else(unix_timestamp_millis);
+        self.seal_state()?;
+        info!(
+            target = "sui_event_subscriber",
+            event = "rotate-tdx-quote-event",
+            payout_policy = ?self.payout_policy,
+            "Generated new secret successfully"
+        );
+        Ok(())
+    }
+
+    /// Returns the category of the given round's secret.
+    ///
+    /// Secrets selected from the committed word list are not picked by theme or difficulty,
+    /// so only the language of the category applies to them.
+    pub(super) fn category_for_round(config: &SecretGuessingConfig, round: u64) -> SecretCategory {
+        let category = config
+            .secret_category
+            .clone()
+            .unwrap_or_default()
+            .category_for_round(round);
+        match config.secret_source {
+            Some(SecretSourceConfig::WordList { .. }) => SecretCategory {
+                language: category.language,
+                ..Default::default()
+            },
+            Some(SecretSourceConfig::Model) | None => category,
+        }
+    }
+
+    /// Seals the current round's state to disk, so that a restart resumes the round
+    /// instead of generating a new secret.
+    pub(super) fn seal_state(&self) -> Result<()> {
+        self.sealed_store.save(&SealedState {
+            secret: self.secret.clone(),
+            random_seed: self.random_seed,
+            client_private_key: self.client_private_key.to_bytes(),
+            hints: self.hints.clone(),
+            secret_epoch_start_ms: self.secret_epoch_start_ms,
+            secret_salt: self.secret_salt,
+            secret_revealed: self.secret_revealed,
+            round: self.round,
+            category: self.category.clone(),
+            payout_policy: self.payout_policy.clone(),
+            winning_event: self.winning_event,
+            salt_chain: self.salt_chain.clone(),
+        })?;
+        Ok(())
+    }
+
+    /// Reveals the current secret and the salt of its commitment on-chain, so that
+    /// anyone can verify that the secret was not changed during the round.
+    ///
+    /// Failing to reveal is logged but not propagated, as it must neither hold up the
+    /// payout of a winner nor the rotation to a new secret. The round stays closed by its
+    /// winning event regardless, and the reveal is retried by `retry_reveal`.
+    #[instrument(level = "info", skip_all)]
+    pub(super) async fn reveal_secret(&mut self) {
+        if self.secret_revealed {
+            return;
+        }
+        self.reveal_attempted_at = crate::store::unix_timestamp_secs();
+        match self
+            .sui_client_ctx
+            .reveal_secret(&self.secret, self.secret_salt.to_vec(), None, None, None)
+            .await
+        {
+            Ok(outcome) => {
+                info!(
+                    target = "sui_event_subscriber",
+                    "Revealed the secret on-chain, tx_hash: {}", outcome.digest
+                );
+                self.secret_revealed = true;
+                if let Err(e) = self.seal_state() {
+                    error!(
+                        target = "sui_event_subscriber",
+                        "Failed to seal the state after revealing the secret: {e}"
+                    );
+                }
+            }
+            Err(e) => {
+                error!(
+                    target = "sui_event_subscriber",
+                    "Failed to reveal the secret on-chain: {e}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use sui_sdk::{
+    rpc_types::Coin,
+    types::{
+        base_types::{ObjectID, ObjectRef, SuiAddress},
+        gas::GasCostSummary,
+    },
+    SuiClient,
+};
+use tracing::{info, instrument, warn};
+
+use crate::{client::SuiClientError, config::SecretGuessingConfig};
+
+/// The default safety margin added to the gas estimated from a dry-run, as a fraction
+const DEFAULT_BUDGET_MARGIN: f64 = 0.2;
+
+/// The default lowest gas budget of a transaction
+const DEFAULT_MIN_GAS_BUDGET: u64 = 2_000_000; // 0.002 SUI
+
+/// The default highest gas budget of a transaction, also used to estimate the gas of a
+/// transaction from a dry-run
+const DEFAULT_MAX_GAS_BUDGET: u64 = 500_000_000; // 0.5 SUI
+
+/// The default number of gas coins the wallet balance is split into
+const DEFAULT_POOL_SIZE: usize = 4;
+
+/// A change to the gas coins of the wallet, needed before the next transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GasRebalance {
+    /// Merge the given coins into the first one, as no single coin can pay the budget
+    Merge(Vec<ObjectRef>),
+
+    /// Split the given coin into new coins of the given amounts, to grow the pool
+    Split { coin: ObjectRef, amounts: Vec<u64> },
+}
+
+/// Keeps track of the gas coins of the agent's wallet.
+///
+/// Gas budgets are estimated from a dry-run, with a safety margin, instead of a fixed
+/// budget that a large transaction could exceed. Each transaction is paid with the
+/// smallest coin that covers its budget, and the wallet balance is checked before each
+/// transaction, to warn, and eventually halt, before the wallet runs dry.
+///
+/// Coins are refreshed before each transaction, and a `SuiClientContext` sends one
+/// transaction at a time, so coins are not reserved. A wallet must thus not be shared
+/// with another process sending transactions at the same time (e.g. the admin CLI), as
+/// both could pick the same coin.
+pub struct GasManager {
+    /// Safety margin added to the gas estimated from a dry-run, as a fraction
+    budget_margin: f64,
+
+    /// Lowest gas budget of a transaction
+    min_budget: u64,
+
+    /// Highest gas budget of a transaction
+    max_budget: u64,
+
+    /// Number of gas coins the wallet balance is split into
+    pool_size: usize,
+
+    /// Wallet balance below which a warning is logged
+    warn_balance: Option<u64>,
+
+    /// Wallet balance below which no transaction is sent anymore
+    halt_balance: Option<u64>,
+
+    /// The SUI coins of the wallet, as of the last refresh
+    coins: Vec<Coin>,
+}
+
+impl Default for GasManager {
+    fn default() -> Self {
+        Self {
+            budget_margin: DEFAULT_BUDGET_MARGIN,
+            min_budget: DEFAULT_MIN_GAS_BUDGET,
+            max_budget: DEFAULT_MAX_GAS_BUDGET,
+            pool_size: DEFAULT_POOL_SIZE,
+            warn_balance: None,
+            halt_balance: None,
+            coins: Vec::new(),
+        }
+    }
+}
+
+impl GasManager {
+    /// Creates the gas manager from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        let gas = config.gas.clone().unwrap_or_default();
+        let min_budget = gas.min_budget.unwrap_or(DEFAULT_MIN_GAS_BUDGET);
+        Self {
+            budget_margin: gas.budget_margin.unwrap_or(DEFAULT_BUDGET_MARGIN).max(0.0),
+            min_budget,
+            max_budget: gas
+                .max_budget
+                .unwrap_or(DEFAULT_MAX_GAS_BUDGET)
+                .max(min_budget),
+            pool_size: gas.pool_size.unwrap_or(DEFAULT_POOL_SIZE).max(1),
+            warn_balance: gas.warn_balance,
+            halt_balance: gas.halt_balance,
+            ..Default::default()
+        }
+    }
+
+    /// The lowest gas budget of a transaction.
+    pub fn min_budget(&self) -> u64 {
+        self.min_budget
+    }
+
+    /// The total balance of the wallet's SUI coins, as of the last refresh.
+    pub fn total_balance(&self) -> u64 {
+        self.coins.iter().map(|coin| coin.balance).sum()
+    }
+
+    /// The budget of the dry-run estimating a transaction's gas, which the largest coin
+    /// must be able to pay, on top of what the calls pay out of it.
+    ///
+    /// # Returns
+    ///
+    /// The budget, or `None` if the largest coin cannot pay at least the minimum budget
+    /// on top of the payment, in which case no transaction can be sent.
+    pub fn probe_budget(&self, payment: u64) -> Option<u64> {
+        let budget = self
+            .coins
+            .iter()
+            .map(|coin| coin.balance)
+            .max()?
+            .saturating_sub(payment)
+            .min(self.max_budget);
+        (budget >= self.min_budget).then_some(budget)
+    }
+
+    /// Estimates the gas budget of a transaction from the gas it used in a dry-run.
+    ///
+    /// The storage rebate is ignored, as it is only credited after the budget is
+    /// checked, and the safety margin covers the state changing between the dry-run
+    /// and the execution.
+    pub fn estimate_budget(&self, gas_used: &GasCostSummary) -> u64 {
+        let gas = gas_used
+            .computation_cost
+            .saturating_add(gas_used.storage_cost);
+        let budget = (gas as f64 * (1.0 + self.budget_margin)).ceil() as u64;
+        budget.clamp(self.min_budget, self.max_budget)
+    }
+
+    /// Fetches the wallet's SUI coins, and checks the wallet balance against the
+    /// configured thresholds.
+    ///
+    /// # Errors
+    ///
+    /// * `SuiClientError::LowGasBalance` - If the balance is below the halt threshold
+    /// * `SuiClientError::RpcError` - If the coins cannot be fetched
+    #[instrument(level = "info", skip_all, fields(owner = %owner))]
+    pub async fn refresh(
+        &mut self,
+        client: &SuiClient,
+        owner: SuiAddress,
+    ) -> Result<u64, SuiClientError> {
+        let mut coins = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = client
+                .coin_read_api()
+                .get_coins(owner, None, cursor, None)
+                .await?;
+            coins.extend(page.data);
+            if !page.has_next_page {
+                break;
+            }
+            cursor = page.next_cursor;
+        }
+        self.coins = coins;
+
+        let balance = self.total_balance();
+        if let Some(threshold) = self.halt_balance.filter(|threshold| balance < *threshold) {
+            return Err(SuiClientError::LowGasBalance { balance, threshold });
+        }
+        if let Some(threshold) = self.warn_balance.filter(|threshold| balance < *threshold) {
+            warn!(
+                target = "gas-manager",
+                balance,
+                threshold,
+                "Wallet balance is running low, top it up before transactions are halted"
+            );
+        }
+        Ok(balance)
+    }
+
+    /// Selects the smallest coin that can pay the given budget.
+    ///
+    /// # Returns
+    ///
+    /// The selected coin, or `None` if no single coin can pay the budget.
+    pub fn select_coin(&self, budget: u64) -> Option<ObjectRef> {
+        self.coins
+            .iter()
+            .filter(|coin| coin.balance >= budget)
+            .min_by_key(|coin| coin.balance)
+            .map(|coin| coin.object_ref())
+    }
+
+    /// Returns the reference of a coin of the wallet, as of the last refresh.
+    pub fn coin_ref(&self, coin: ObjectID) -> Option<ObjectRef> {
+        self.coins
+            .iter()
+            .find(|c| c.coin_object_id == coin)
+            .map(|c| c.object_ref())
+    }
+
+    /// Returns the reference of the largest coin, which pays for the dry-run estimating
+    /// a transaction's gas.
+    pub fn largest_coin(&self) -> Option<ObjectRef> {
+        self.coins
+            .iter()
+            .max_by_key(|coin| coin.balance)
+            .map(|coin| coin.object_ref())
+    }
+
+    /// Plans the change to the gas coins needed before sending a transaction with the
+    /// given budget, if any.
+    ///
+    /// When no single coin can pay the budget, but the coins can together, they are
+    /// merged. When the pool has fewer coins than configured, and its largest coin can
+    /// pay for several of the largest budgets, it is split.
+    pub fn plan_rebalance(&self, budget: u64) -> Option<GasRebalance> {
+        let mut coins = self.coins.iter().collect::<Vec<_>>();
+        coins.sort_by_key(|coin| std::cmp::Reverse(coin.balance));
+        let largest = coins.first()?;
+        if largest.balance < budget {
+            let total = coins.iter().map(|coin| coin.balance).sum::<u64>();
+            return (total >= budget && coins.len() > 1).then(|| {
+                GasRebalance::Merge(coins.iter().map(|coin| coin.object_ref()).collect())
+            });
+        }
+        let missing = self.pool_size.saturating_sub(coins.len());
+        // Each new coin holds a maximum budget, and the split coin keeps at least as much
+        let splittable = (largest.balance / self.max_budget).saturating_sub(1) as usize;
+        let count = missing.min(splittable);
+        (count > 0).then(|| GasRebalance::Split {
+            coin: largest.object_ref(),
+            amounts: vec![self.max_budget; count],
+        })
+    }
+
+    /// Logs the outcome of a gas coin rebalance.
+    pub(crate) fn log_rebalance(rebalance: &GasRebalance, tx_hash: &str) {
+        match rebalance {
+            GasRebalance::Merge(coins) => info!(
+                target = "gas-manager",
+                num_coins = coins.len(),
+                "Merged gas coins, tx_hash: {tx_hash}"
+            ),
+            GasRebalance::Split { amounts, .. } => info!(
+                target = "gas-manager",
+                num_coins = amounts.len(),
+                "Split a gas coin into new ones, tx_hash: {tx_hash}"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_sdk::types::{
+        base_types::SequenceNumber,
+        digests::{ObjectDigest, TransactionDigest},
+    };
+
+    use super::*;
+
+    fn coin(balance: u64) -> Coin {
+        Coin {
+            coin_type: "0x2::sui::SUI".to_string(),
+            coin_object_id: ObjectID::random(),
+            version: SequenceNumber::from_u64(1),
+            digest: ObjectDigest::random(),
+            balance,
+            previous_transaction: TransactionDigest::random(),
+        }
+    }
+
+    fn manager(balances: &[u64]) -> GasManager {
+        GasManager {
+            min_budget: 1_000,
+            max_budget: 10_000,
+            pool_size: 4,
+            coins: balances.iter().copied().map(coin).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn estimate_budget_adds_the_margin_within_bounds() {
+        let manager = manager(&[]);
+        assert_eq!(
+            manager.estimate_budget(&GasCostSummary::new(3_000, 2_000, 4_000, 0)),
+            6_000
+        );
+        assert_eq!(
+            manager.estimate_budget(&GasCostSummary::new(10, 10, 0, 0)),
+            1_000
+        );
+        assert_eq!(
+            manager.estimate_budget(&GasCostSummary::new(50_000, 0, 0, 0)),
+            10_000
+        );
+    }
+
+    #[test]
+    fn probe_budget_leaves_the_payment_and_enforces_the_minimum() {
+        assert_eq!(manager(&[50_000]).probe_budget(0), Some(10_000));
+        assert_eq!(manager(&[5_000, 8_000]).probe_budget(2_000), Some(6_000));
+        assert_eq!(manager(&[5_000]).probe_budget(4_500), None);
+        assert_eq!(manager(&[5_000]).probe_budget(6_000), None);
+        assert_eq!(manager(&[]).probe_budget(0), None);
+    }
+
+    #[test]
+    fn select_coin_picks_the_smallest_sufficient_coin() {
+        let manager = manager(&[9_000, 3_000, 5_000]);
+        let selected = manager.select_coin(4_000).unwrap();
+        assert_eq!(selected, manager.coins[2].object_ref());
+        assert!(manager.select_coin(20_000).is_none());
+        assert_eq!(manager.largest_coin(), Some(manager.coins[0].object_ref()));
+    }
+
+    #[test]
+    fn plan_rebalance_merges_when_no_single_coin_pays() {
+        let manager = manager(&[3_000, 4_000, 2_000]);
+        let Some(GasRebalance::Merge(coins)) = manager.plan_rebalance(8_000) else {
+            panic!("expected a merge");
+        };
+        // The largest coin comes first, as the others are merged into it
+        assert_eq!(coins[0], manager.coins[1].object_ref());
+        assert_eq!(coins.len(), 3);
+    }
+
+    #[test]
+    fn plan_rebalance_does_nothing_when_coins_cannot_pay_together() {
+        assert_eq!(manager(&[3_000, 4_000]).plan_rebalance(8_000), None);
+        assert_eq!(manager(&[3_000]).plan_rebalance(8_000), None);
+        assert_eq!(manager(&[]).plan_rebalance(1_000), None);
+    }
+
+    #[test]
+    fn plan_rebalance_splits_up_to_the_pool_size() {
+        // The largest coin keeps a maximum budget, the rest is split into 3 missing coins
+        let manager = manager(&[100_000]);
+        assert_eq!(
+            manager.plan_rebalance(1_000),
+            Some(GasRebalance::Split {
+                coin: manager.coins[0].object_ref(),
+                amounts: vec![10_000; 3],
+            })
+        );
+        // Only as many coins as the largest coin can hold, besides its own maximum budget
+        let manager = self::manager(&[25_000, 1_000]);
+        assert_eq!(
+            manager.plan_rebalance(1_000),
+            Some(GasRebalance::Split {
+                coin: manager.coins[0].object_ref(),
+                amounts: vec![10_000],
+            })
+        );
+    }
+
+    #[test]
+    fn plan_rebalance_keeps_a_full_pool() {
+        assert_eq!(
+            manager(&[100_000, 10_000, 10_000, 10_000]).plan_rebalance(1_000),
+            None
+        );
+        assert_eq!(manager(&[15_000]).plan_rebalance(1_000), None);
+    }
+}
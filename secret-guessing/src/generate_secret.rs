@@ -1,41 +1,80 @@
 use serde_json::json;
 use thiserror::Error;
-use tracing::instrument;
+use tracing::{info, instrument, warn};
 use x25519_dalek::{PublicKey, StaticSecret};
 
 use crate::{
     atoma::{AtomaSdk, AtomaSdkError},
     client::{SuiClientContext, SuiClientError},
+    commitment::{self, COMMITMENT_SALT_SIZE},
+    config::{SecretCategory, SecretGuessingConfig},
     engine::prompts::SecretPromptResponse,
+    sealing::{self, SealingError},
+    secret_validation::{DifficultyEstimate, SecretValidation, SecretValidator},
+    selection::SecretSelector,
+    store::StoreError,
 };
 
 type Result<T> = std::result::Result<T, GenerateSecretError>;
 
+/// A newly generated secret.
+pub struct GeneratedSecret {
+    /// The secret
+    pub secret: String,
+
+    /// The estimated difficulty of the secret
+    pub difficulty: DifficultyEstimate,
+}
+
+/// What a new secret is generated for: the round it is picked for, and its commitment.
+pub struct SecretRequest<'a> {
+    /// The prompt text used to generate the secret
+    pub prompt: String,
+
+    /// The round's random seed, also the seed of the first inference request
+    pub random_seed: u64,
+
+    /// The salt of the secret's commitment, drawn from the round's salt chain
+    pub salt: [u8; COMMITMENT_SALT_SIZE],
+
+    /// The theme, language and difficulty the secret is picked for
+    pub category: &'a SecretCategory,
+}
+
 /// Generates a new secret using AI completion while establishing a secure connection with the node.
 ///
 /// This function performs the following steps:
-/// 1. Submits the client's public key to the Sui network with a TDX quote for attestation
-/// 2. Makes a confidential chat completion request to generate a secret
-/// 3. Parses and returns the generated secret
+/// 1. Picks the secret: either selects it from the committed word list with the random
+///    seed, or makes confidential chat completion requests until the model picks a secret
+///    that passes validation. A rejected secret is sent back to the model along with the
+///    reason it was rejected, until the maximum number of attempts is reached
+/// 2. Submits the client's public key to the Sui network with a TDX quote for attestation,
+///    and publishes a salted hash commitment to the secret in the same transaction, so
+///    that players can later verify that the secret was not changed during the round
+/// 3. Returns the generated secret
 ///
 /// # Arguments
 ///
 /// * `atoma_sdk` - Reference to the Atoma SDK for making confidential AI completions
 /// * `client_public_key` - The client's X25519 public key for secure communication
-/// * `generate_secret_prompt` - The prompt text used to generate the secret
-/// * `model` - The name/identifier of the AI model to use
+/// * `request` - The prompt, random seed, salt and category of the round
+/// * `config` - The agent's configuration, with the model and the attestation settings
+/// * `secret_selector` - The selector of secrets from the committed word list, in verifiable mode
+/// * `secret_validator` - The validator of the secrets picked by the model
 /// * `sui_client_ctx` - Reference to the Sui client context for network operations
 ///
 /// # Returns
 ///
-/// Returns a `Result<String>` containing the generated secret if successful.
+/// Returns a `Result<GeneratedSecret>` containing the generated secret if successful.
 ///
 /// # Errors
 ///
 /// This function can return the following errors:
-/// * `GenerateSecretError::FailedToSubmitNodePublicKey` - If registering the public key with the network fails
 /// * `GenerateSecretError::FailedToGenerateChatCompletions` - If the AI completion request fails
-/// * `GenerateSecretError::FailedToParseSecretPromptResponse` - If parsing the AI response fails
+/// * `GenerateSecretError::NoValidSecret` - If no valid secret was picked within the maximum number of attempts
+/// * `GenerateSecretError::FailedToSubmitNodePublicKey` - If registering the public key or publishing the commitment fails
+/// * `GenerateSecretError::SealingError` - If no TDX quote can be generated outside of development
+/// * `GenerateSecretError::StoreError` - If the history of past secrets cannot be read
 ///
 /// # Instrumentation
 ///
@@ -44,43 +83,137 @@ type Result<T> = std::result::Result<T, GenerateSecretError>;
     level = "info",
     skip_all,
     fields(
-        generate_secret_prompt = %generate_secret_prompt,
-        model = %model,
+        generate_secret_prompt = %request.prompt,
+        model = %config.model,
     )
 )]
 pub async fn generate_new_secret(
     atoma_sdk: &AtomaSdk,
     client_private_key: &StaticSecret,
-    generate_secret_prompt: String,
-    model: String,
-    random_seed: u64,
+    request: SecretRequest<'_>,
+    config: &SecretGuessingConfig,
+    secret_selector: Option<&SecretSelector>,
+    secret_validator: &SecretValidator,
     sui_client_ctx: &mut SuiClientContext,
-) -> Result<String> {
+) -> Result<GeneratedSecret> {
+    let SecretRequest {
+        prompt: generate_secret_prompt,
+        random_seed,
+        salt,
+        category,
+    } = request;
+    let (secret, difficulty) = match secret_selector {
+        Some(secret_selector) => {
+            let selected = secret_selector.select(random_seed, &salt);
+            info!(
+                target = "generate-secret",
+                index = selected.index,
+                num_words = secret_selector.num_words(),
+                difficulty = ?selected.difficulty.difficulty,
+                "Selected the secret from the committed word list"
+            );
+            (selected.secret, selected.difficulty)
+        }
+        None => {
+            pick_secret_with_model(
+                atoma_sdk,
+                client_private_key,
+                generate_secret_prompt,
+                config.model.clone(),
+                random_seed,
+                category,
+                secret_validator,
+            )
+            .await?
+        }
+    };
+
     let client_public_key = PublicKey::from(client_private_key);
-    // let tdx_quote_bytes = tdx::generate_tdx_quote_bytes(&mut rng);
-    // TODO: Remove this once we have a real TDX quote
-    let tdx_quote_bytes = vec![0; 32];
+    // The quote binds the public key, so that the attestation vouches for it
+    let tdx_quote_bytes = sealing::attestation_quote(config, client_public_key.as_bytes())?;
+    let commitment = commitment::compute_commitment(&secret, &salt);
     sui_client_ctx
-        .submit_node_public_key(client_public_key, tdx_quote_bytes, None, None, None)
+        .submit_node_public_key_and_commit_secret(
+            client_public_key,
+            tdx_quote_bytes,
+            commitment.to_vec(),
+            None,
+            None,
+            None,
+        )
         .await?;
 
-    let chat_completions_request = serde_json::from_value(json!({
-        "model": model,
-        "messages": [
-            {"role": "system", "content": generate_secret_prompt},
-        ],
-        "seed": random_seed,
-    }))?;
+    Ok(GeneratedSecret { secret, difficulty })
+}
 
-    let response_body = atoma_sdk
-        .confidential_chat_completions(&client_private_key, chat_completions_request)
-        .await?;
+/// Asks the model for a secret, until it picks one that passes validation.
+///
+/// A rejected secret is sent back to the model along with the reason it was rejected,
+/// and each attempt uses the next random seed.
+///
+/// # Errors
+///
+/// Returns `GenerateSecretError::NoValidSecret` if no valid secret was picked within the
+/// maximum number of attempts, or any error of the chat completion requests.
+async fn pick_secret_with_model(
+    atoma_sdk: &AtomaSdk,
+    client_private_key: &StaticSecret,
+    generate_secret_prompt: String,
+    model: String,
+    random_seed: u64,
+    category: &SecretCategory,
+    secret_validator: &SecretValidator,
+) -> Result<(String, DifficultyEstimate)> {
+    let max_attempts = secret_validator.max_attempts();
+    let mut messages = vec![json!({"role": "system", "content": generate_secret_prompt})];
+    let mut accepted = None;
+    for attempt in 0..max_attempts {
+        let chat_completions_request = serde_json::from_value(json!({
+            "model": model,
+            "messages": messages,
+            "seed": random_seed.wrapping_add(u64::from(attempt)),
+        }))?;
 
-    let secret = serde_json::from_str::<SecretPromptResponse>(
-        &response_body.choices[0].message.content.clone(),
-    )?;
+        let response_body = atoma_sdk
+            .confidential_chat_completions(client_private_key, chat_completions_request)
+            .await?;
+        let content = response_body.choices[0].message.content.clone();
 
-    Ok(secret.secret)
+        let rejection = match serde_json::from_str::<SecretPromptResponse>(&content) {
+            Ok(response) => match secret_validator.validate(&response.secret, category)? {
+                SecretValidation::Valid { secret, difficulty } => {
+                    accepted = Some((secret, difficulty));
+                    break;
+                }
+                SecretValidation::Rejected(reason) => reason,
+            },
+            Err(e) => format!("The response is not valid JSON of the requested format: {e}"),
+        };
+        // The rejection reason is not logged verbatim, as it may quote the candidate secret
+        warn!(
+            target = "generate-secret",
+            attempt = attempt + 1,
+            max_attempts,
+            "The model picked an invalid secret, asking for another one"
+        );
+        messages.push(json!({"role": "assistant", "content": content}));
+        messages.push(json!({
+            "role": "user",
+            "content": format!(
+                "This secret is rejected: {rejection}. Pick another secret following the same rules, and answer in the same JSON format."
+            ),
+        }));
+    }
+    let Some((secret, difficulty)) = accepted else {
+        return Err(GenerateSecretError::NoValidSecret(max_attempts));
+    };
+    info!(
+        target = "generate-secret",
+        difficulty = ?difficulty.difficulty,
+        score = difficulty.score,
+        "The model picked a valid secret"
+    );
+    Ok((secret, difficulty))
 }
 
 #[derive(Error, Debug)]
@@ -93,4 +226,13 @@ pub enum GenerateSecretError {
 
     #[error("Failed to parse secret prompt response")]
     FailedToParseSecretPromptResponse(#[from] serde_json::Error),
+
+    #[error("The model picked no valid secret in {0} attempts")]
+    NoValidSecret(u32),
+
+    #[error("Failed to access the secret history: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Failed to attest the public key: {0}")]
+    SealingError(#[from] SealingError),
 }
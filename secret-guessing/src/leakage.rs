@@ -0,0 +1,245 @@
+use tracing::{info, instrument};
+use x25519_dalek::StaticSecret;
+
+use crate::{
+    atoma::{AtomaSdk, AtomaSdkError},
+    config::SecretGuessingConfig,
+    matcher,
+    types::CreateEmbeddingRequest,
+};
+
+/// The default maximum edit distance between a word of a hint and the secret,
+/// at or below which the word is deemed a variant of the secret
+pub const DEFAULT_MAX_EDIT_DISTANCE: usize = 2;
+
+/// The default cosine similarity between the embeddings of a hint and of the secret,
+/// above which the hint is deemed to give the secret away
+pub const DEFAULT_MAX_EMBEDDING_SIMILARITY: f32 = 0.85;
+
+/// The default number of times a leaking hint is regenerated before giving up
+pub const DEFAULT_MAX_HINT_RETRIES: u32 = 3;
+
+/// Words shorter than this are never compared to the secret by edit distance,
+/// as almost any short word is a couple of edits away from any other
+const MIN_EDIT_DISTANCE_WORD_LENGTH: usize = 4;
+
+/// Secrets shorter than this are not searched for across word boundaries, as
+/// they would too often appear by accident when the hint's words are concatenated
+const MIN_CROSS_WORD_SECRET_LENGTH: usize = 6;
+
+/// A filter that rejects generated hints that give the secret away, before they
+/// are ever published.
+///
+/// The lexical checks catch the secret itself, spelled across words or letter by letter,
+/// its close variants and its anagrams. If an embedding model is configured, hints that
+/// are semantically too close to the secret are rejected as well.
+pub struct HintLeakageFilter {
+    /// The Atoma SDK instance for the embedding model, if embedding similarity is checked
+    embedding_sdk: Option<AtomaSdk>,
+
+    /// The embedding model, if embedding similarity is checked
+    embedding_model: Option<String>,
+
+    /// Maximum edit distance at or below which a word is a variant of the secret
+    max_edit_distance: usize,
+
+    /// Maximum cosine similarity allowed between the hint and the secret
+    max_embedding_similarity: f32,
+}
+
+impl HintLeakageFilter {
+    /// Creates the hint leakage filter from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        let leakage = config.hint_leakage.clone().unwrap_or_default();
+        let embedding_sdk = leakage
+            .embedding_model
+            .clone()
+            .map(|model| AtomaSdk::new(config.atoma_api_key.clone(), model));
+        Self {
+            embedding_sdk,
+            embedding_model: leakage.embedding_model,
+            max_edit_distance: leakage
+                .max_edit_distance
+                .unwrap_or(DEFAULT_MAX_EDIT_DISTANCE),
+            max_embedding_similarity: leakage
+                .max_embedding_similarity
+                .unwrap_or(DEFAULT_MAX_EMBEDDING_SIMILARITY),
+        }
+    }
+
+    /// Checks whether a hint leaks the secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_private_key` - The client's X25519 private key, for the embeddings request
+    /// * `hint` - The generated hint
+    /// * `secret` - The current secret
+    ///
+    /// # Returns
+    ///
+    /// `Some(reason)` if the hint leaks the secret and must not be published, `None` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AtomaSdkError` if the embeddings request fails.
+    #[instrument(level = "info", skip_all)]
+    pub async fn check(
+        &self,
+        client_private_key: &StaticSecret,
+        hint: &str,
+        secret: &str,
+    ) -> Result<Option<String>, AtomaSdkError> {
+        if let Some(reason) = self.check_lexical(hint, secret) {
+            return Ok(Some(reason));
+        }
+        let (Some(embedding_sdk), Some(embedding_model)) =
+            (&self.embedding_sdk, &self.embedding_model)
+        else {
+            return Ok(None);
+        };
+
+        let response = embedding_sdk
+            .confidential_embeddings(
+                client_private_key,
+                CreateEmbeddingRequest {
+                    model: embedding_model.clone(),
+                    input: vec![secret.to_string(), hint.to_string()],
+                    dimensions: None,
+                    user: None,
+                },
+            )
+            .await?;
+        let mut data = response.data;
+        data.sort_by_key(|embedding| embedding.index);
+        let [secret_embedding, hint_embedding] = data.as_slice() else {
+            return Err(AtomaSdkError::EmbeddingsResponseError(format!(
+                "Expected 2 embeddings, received: {}",
+                data.len()
+            )));
+        };
+        let similarity = cosine_similarity(&secret_embedding.embedding, &hint_embedding.embedding);
+        info!(
+            target = "hint-leakage",
+            similarity = similarity,
+            "Computed the embedding similarity between the hint and the secret"
+        );
+        if similarity > self.max_embedding_similarity {
+            return Ok(Some(format!(
+                "The hint is too close in meaning to the secret (similarity {similarity:.3} above {})",
+                self.max_embedding_similarity
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Runs the lexical checks of the filter, which need no inference request.
+    ///
+    /// # Returns
+    ///
+    /// `Some(reason)` if the hint leaks the secret, `None` otherwise.
+    pub fn check_lexical(&self, hint: &str, secret: &str) -> Option<String> {
+        let secret = matcher::normalize(secret);
+        if secret.is_empty() {
+            return None;
+        }
+        let secret_stem = matcher::stem(&secret);
+        let secret_length = secret.chars().count();
+        let words = hint
+            .split(|c: char| !c.is_alphanumeric())
+            .map(matcher::normalize)
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        // Short secrets are substrings of too many unrelated words, so only their inflections count
+        let contains_secret = |word: &&String| {
+            if secret_stem.chars().count() < MIN_EDIT_DISTANCE_WORD_LENGTH {
+                matcher::stem(word) == secret_stem
+            } else {
+                word.contains(&secret_stem)
+            }
+        };
+        if let Some(word) = words.iter().find(contains_secret) {
+            return Some(format!("The hint word {word:?} contains the secret"));
+        }
+        if secret_length >= MIN_CROSS_WORD_SECRET_LENGTH && words.concat().contains(&secret) {
+            return Some("The hint contains the secret, split across several words".to_string());
+        }
+        if spelled_out(&words).iter().any(|run| run.contains(&secret)) {
+            return Some("The hint spells the secret letter by letter".to_string());
+        }
+
+        // Tolerate fewer edits on short secrets, to avoid rejecting unrelated words
+        let max_edit_distance = self.max_edit_distance.min(secret_length / 3);
+        if let Some(word) = words.iter().find(|word| {
+            word.chars().count() >= MIN_EDIT_DISTANCE_WORD_LENGTH
+                && edit_distance(word, &secret) <= max_edit_distance
+        }) {
+            return Some(format!(
+                "The hint word {word:?} is a close variant of the secret"
+            ));
+        }
+
+        let secret_letters = sorted_chars(&secret);
+        if let Some(word) = words
+            .iter()
+            .find(|word| word.len() == secret.len() && sorted_chars(word) == secret_letters)
+        {
+            return Some(format!(
+                "The hint word {word:?} is an anagram of the secret"
+            ));
+        }
+        None
+    }
+}
+
+/// Joins the runs of consecutive single-character words of a hint, which is
+/// how a word is spelled letter by letter (e.g. `"k-i-t-e"` or `"K I T E"`).
+fn spelled_out(words: &[String]) -> Vec<String> {
+    let mut runs = vec![];
+    let mut run = String::new();
+    for word in words {
+        if word.chars().count() == 1 {
+            run.push_str(word);
+        } else if !run.is_empty() {
+            runs.push(std::mem::take(&mut run));
+        }
+    }
+    if !run.is_empty() {
+        runs.push(run);
+    }
+    runs
+}
+
+/// Returns the characters of a word, sorted, to compare words for anagrams.
+fn sorted_chars(word: &str) -> Vec<char> {
+    let mut chars = word.chars().collect::<Vec<_>>();
+    chars.sort_unstable();
+    chars
+}
+
+/// Computes the Levenshtein distance between two strings, in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Computes the cosine similarity of two embeddings, or `0.0` if either of them is zero.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
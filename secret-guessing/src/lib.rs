@@ -1,8 +1,27 @@
+pub mod announcer;
+pub mod approval;
 pub mod atoma;
+pub mod audit;
 pub mod client;
+pub mod commitment;
 pub mod config;
+pub mod dead_letter;
 pub mod engine;
+pub mod gas;
 pub mod generate_secret;
+pub mod injection;
+pub mod leakage;
+pub mod matcher;
+pub mod moderation;
+pub mod payout;
+pub mod player;
+pub mod quorum;
+pub mod review;
+pub mod sealing;
+pub mod secret_validation;
+pub mod selection;
+pub mod store;
+pub mod transactions;
 // pub mod tdx;
 pub mod types;
 
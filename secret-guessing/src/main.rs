@@ -0,0 +1,404 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use fastcrypto::encoding::{Encoding, Hex};
+use secret_guessing::{
+    approval::ApprovalQueue,
+    client::SuiClientContext,
+    commitment,
+    config::SecretGuessingConfig,
+    dead_letter::DeadLetterStore,
+    gas::GasManager,
+    review::{ReviewQueue, ReviewResolution},
+    selection::{self, SecretSelector},
+};
+use sui_sdk::{
+    types::base_types::{ObjectID, SuiAddress},
+    wallet_context::WalletContext,
+};
+
+/// Operator command line interface for the Secret Guessing agent
+#[derive(Debug, Parser)]
+#[command(name = "secret-guessing", version, about)]
+struct Cli {
+    /// Path to the Secret Guessing TOML configuration file
+    #[arg(long, default_value = "config.toml")]
+    config: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the operator functions of the game, from the wallet owning the admin capability
+    Admin {
+        /// Path to the Sui client configuration of the admin wallet
+        #[arg(long)]
+        wallet_config: PathBuf,
+
+        /// The ID of the Secret Guessing database object
+        #[arg(long)]
+        db: String,
+
+        #[command(subcommand)]
+        command: AdminCommand,
+    },
+
+    /// Inspect, approve and reject the high-value payouts waiting for an operator
+    #[command(subcommand)]
+    Approval(ApprovalCommand),
+
+    /// Inspect and manage events that failed to be handled by the engine
+    #[command(subcommand)]
+    DeadLetter(DeadLetterCommand),
+
+    /// Inspect and manage guesses on which the judges did not reach a quorum
+    #[command(subcommand)]
+    Review(ReviewCommand),
+
+    /// Check that a revealed secret was selected from the committed word list
+    VerifySecret {
+        /// The round's random seed, as emitted in the `RotateTdxQuoteEvent`
+        #[arg(long)]
+        random_seed: u64,
+
+        /// The revealed salt, hex-encoded
+        #[arg(long)]
+        salt: String,
+
+        /// The revealed secret
+        #[arg(long)]
+        secret: String,
+
+        /// The on-chain commitment to the secret, hex-encoded, to check the reveal against
+        #[arg(long)]
+        commitment: Option<String>,
+
+        /// The previous round's revealed salt, hex-encoded, which commits to this round's
+        /// salt. Without it, the salt may have been picked after the random seed was known
+        #[arg(long)]
+        previous_salt: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AdminCommand {
+    /// Print the on-chain state of the game
+    State,
+
+    /// Pause the game, so that guesses are refused
+    Pause,
+
+    /// Unpause the game, so that guesses are accepted again
+    Unpause,
+
+    /// Change the fee of a guess
+    SetFee {
+        /// The new fee of a guess, in MIST
+        fee: u64,
+    },
+
+    /// Add funds from the admin wallet to the treasury pool
+    SeedTreasury {
+        /// The amount to add, in MIST
+        amount: u64,
+    },
+
+    /// Hand the admin capability over to a new admin
+    RotateAdmin {
+        /// The address of the new admin
+        new_admin: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ApprovalCommand {
+    /// List the queued payouts, along with each judge's vote and response signature
+    List {
+        /// Also list the payouts that were already decided
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Approve a pending payout, for the running engine to send it
+    Approve {
+        /// The approval entry identifier, in the form `<tx_digest>:<event_seq>`
+        id: String,
+    },
+
+    /// Reject a pending payout, so that its guess is treated as an incorrect one
+    Reject {
+        /// The approval entry identifier, in the form `<tx_digest>:<event_seq>`
+        id: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DeadLetterCommand {
+    /// List all dead-lettered events
+    List,
+
+    /// Schedule a dead-lettered event for an immediate retry by the running engine
+    Retry {
+        /// The dead-letter entry identifier, in the form `<tx_digest>:<event_seq>`
+        id: String,
+    },
+
+    /// Discard a dead-lettered event, so that it is never retried
+    Discard {
+        /// The dead-letter entry identifier, in the form `<tx_digest>:<event_seq>`
+        id: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ReviewCommand {
+    /// List all guesses waiting for review, along with each judge's vote
+    List,
+
+    /// Resolve a guess, for the running engine to settle it like any judged guess
+    Resolve {
+        /// The review entry identifier, in the form `<tx_digest>:<event_seq>`
+        id: String,
+
+        /// The guess is correct: it is paid out, or queued for payout approval
+        #[arg(
+            long,
+            conflicts_with = "incorrect",
+            required_unless_present = "incorrect"
+        )]
+        correct: bool,
+
+        /// The guess is incorrect
+        #[arg(long)]
+        incorrect: bool,
+    },
+
+    /// Remove a guess from the review queue, without settling it
+    Dismiss {
+        /// The review entry identifier, in the form `<tx_digest>:<event_seq>`
+        id: String,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = SecretGuessingConfig::from_file_path(&cli.config)?;
+
+    match cli.command {
+        Command::Admin {
+            wallet_config,
+            db,
+            command,
+        } => handle_admin_command(&config, &wallet_config, &db, command),
+        Command::Approval(command) => handle_approval_command(&config, command),
+        Command::DeadLetter(command) => handle_dead_letter_command(&config, command),
+        Command::Review(command) => handle_review_command(&config, command),
+        Command::VerifySecret {
+            random_seed,
+            salt,
+            secret,
+            commitment,
+            previous_salt,
+        } => handle_verify_secret_command(
+            &config,
+            random_seed,
+            &salt,
+            &secret,
+            commitment,
+            previous_salt,
+        ),
+    }
+}
+
+fn handle_admin_command(
+    config: &SecretGuessingConfig,
+    wallet_config: &Path,
+    db: &str,
+    command: AdminCommand,
+) -> Result<()> {
+    let wallet_context = WalletContext::new(
+        wallet_config,
+        config.request_timeout.map(Duration::from_millis),
+        None,
+    )?;
+    let mut sui_client_ctx = SuiClientContext::new(
+        ObjectID::from_str(db)?,
+        ObjectID::from_str(&config.package_id)?,
+        wallet_context,
+    )
+    .with_gas_manager(GasManager::from_config(config));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let outcome = match command {
+            AdminCommand::State => {
+                let state = sui_client_ctx.fetch_game_state().await?;
+                println!(
+                    "treasury_pool_balance={} fee={} guess_count={} epoch={} public_key={}",
+                    state.treasury_pool_balance,
+                    state.fee,
+                    state.guess_count,
+                    state.epoch,
+                    Hex::encode(&state.public_key)
+                );
+                return Ok(());
+            }
+            AdminCommand::Pause => sui_client_ctx.set_paused(true, None, None, None).await?,
+            AdminCommand::Unpause => sui_client_ctx.set_paused(false, None, None, None).await?,
+            AdminCommand::SetFee { fee } => sui_client_ctx.set_fee(fee, None, None, None).await?,
+            AdminCommand::SeedTreasury { amount } => {
+                sui_client_ctx
+                    .seed_treasury(amount, None, None, None)
+                    .await?
+            }
+            AdminCommand::RotateAdmin { new_admin } => {
+                sui_client_ctx
+                    .rotate_admin(SuiAddress::from_str(&new_admin)?, None, None, None)
+                    .await?
+            }
+        };
+        println!(
+            "tx_hash={} gas_cost={}",
+            outcome.digest,
+            outcome.net_gas_cost()
+        );
+        Ok(())
+    })
+}
+
+fn handle_dead_letter_command(
+    config: &SecretGuessingConfig,
+    command: DeadLetterCommand,
+) -> Result<()> {
+    let store = DeadLetterStore::from_config(config);
+    match command {
+        DeadLetterCommand::List => print_entries(store.list()?, "No dead-lettered events"),
+        DeadLetterCommand::Retry { id } => {
+            if !store.schedule_retry(&id)? {
+                bail!("No dead-lettered event with id `{id}`");
+            }
+            println!("Scheduled `{id}` for an immediate retry");
+        }
+        DeadLetterCommand::Discard { id } => {
+            if store.remove(&id)?.is_none() {
+                bail!("No dead-lettered event with id `{id}`");
+            }
+            println!("Discarded `{id}`");
+        }
+    }
+    Ok(())
+}
+
+fn handle_approval_command(config: &SecretGuessingConfig, command: ApprovalCommand) -> Result<()> {
+    let queue = ApprovalQueue::from_config(config);
+    match command {
+        ApprovalCommand::List { all } => {
+            let entries = if all { queue.list()? } else { queue.pending()? };
+            print_entries(entries, "No payouts waiting for approval");
+        }
+        ApprovalCommand::Approve { id } => {
+            queue.approve(&id, "cli")?;
+            println!("Approved `{id}`, the running engine sends the payout");
+        }
+        ApprovalCommand::Reject { id } => {
+            queue.reject(&id, "cli")?;
+            println!("Rejected `{id}`");
+        }
+    }
+    Ok(())
+}
+
+fn handle_review_command(config: &SecretGuessingConfig, command: ReviewCommand) -> Result<()> {
+    let queue = ReviewQueue::from_config(config);
+    match command {
+        ReviewCommand::List => print_entries(queue.list()?, "No guesses waiting for review"),
+        ReviewCommand::Resolve { id, correct, .. } => {
+            let resolution = if correct {
+                ReviewResolution::Correct
+            } else {
+                ReviewResolution::Incorrect
+            };
+            queue.resolve(&id, resolution)?;
+            println!("Resolved `{id}` as {resolution:?}, the running engine settles the guess");
+        }
+        ReviewCommand::Dismiss { id } => {
+            if queue.remove(&id)?.is_none() {
+                bail!("No guess waiting for review with id `{id}`");
+            }
+            println!("Dismissed `{id}`");
+        }
+    }
+    Ok(())
+}
+
+/// Prints the entries of a store, one per line, or `empty` if there are none.
+fn print_entries<T: Display>(entries: Vec<T>, empty: &str) {
+    if entries.is_empty() {
+        println!("{empty}");
+    }
+    for entry in entries {
+        println!("{entry}");
+    }
+}
+
+fn handle_verify_secret_command(
+    config: &SecretGuessingConfig,
+    random_seed: u64,
+    salt: &str,
+    secret: &str,
+    commitment: Option<String>,
+    previous_salt: Option<String>,
+) -> Result<()> {
+    let selector = SecretSelector::from_config(config)?;
+    let salt = decode_hex(salt)?;
+    println!(
+        "word_list_digest={} num_words={}",
+        Hex::encode(selector.word_list_digest()),
+        selector.num_words()
+    );
+    if let Some(commitment) = commitment {
+        if !commitment::verify_reveal(&decode_hex(&commitment)?, secret, &salt) {
+            bail!("The secret and salt do not match the commitment");
+        }
+        println!("The secret and salt match the commitment");
+    }
+    match previous_salt {
+        Some(previous_salt) => {
+            if !selection::verify_chained_salt(&decode_hex(&previous_salt)?, &salt) {
+                bail!("The salt is not the one committed to by the previous round's salt");
+            }
+            println!("The salt was committed to by the previous round's salt");
+        }
+        None => {
+            println!("The salt was not checked against the previous round's salt, so it may have been picked after the random seed was known");
+        }
+    }
+    let selected = selector.select(random_seed, &salt);
+    if selected.secret != secret {
+        bail!(
+            "The secret was not selected from the word list, expected {:?} at index {}",
+            selected.secret,
+            selected.index
+        );
+    }
+    println!(
+        "The secret was selected from the word list, at index {}",
+        selected.index
+    );
+    Ok(())
+}
+
+/// Decodes a hex-encoded command line argument.
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    Hex::decode(value).map_err(|e| anyhow!("Invalid hex value {value:?}: {e}"))
+}
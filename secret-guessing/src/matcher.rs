@@ -0,0 +1,325 @@
+use std::collections::{HashMap, HashSet};
+
+use unicode_normalization::UnicodeNormalization;
+
+/// How many times longer than the secret a guess may be before it is deemed a miss
+const MAX_LENGTH_RATIO: usize = 4;
+
+/// The number of characters a guess may exceed the secret by, whatever its ratio, so
+/// that the length threshold never rejects a short translation or phrasing of a short secret
+const MIN_LENGTH_SLACK: usize = 24;
+
+/// The outcome of matching a guess against the secret locally.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchOutcome {
+    /// The guess definitely matches the secret, with an explanation of why
+    Correct(String),
+
+    /// The guess definitely does not match the secret, with an explanation of why
+    Incorrect(String),
+
+    /// The matcher cannot decide, so the guess needs to be judged by the model
+    Ambiguous,
+}
+
+/// A deterministic matcher that settles the obvious cases of a guess without
+/// querying the model.
+///
+/// Both the guess and the secret are normalized (Unicode NFKC, case folding, stripping
+/// of punctuation and whitespace) before being compared. A guess is only deemed correct
+/// if it is the secret or one of its configured synonyms: the singular or plural form of
+/// the secret is left to the model, as suffix rules cannot tell a plural from a different
+/// word sharing its stem.
+///
+/// A guess is deemed incorrect if it has no letters, if it is far longer than the secret,
+/// or if the judges already deemed it (or its singular or plural form) incorrect for the
+/// current secret. Guesses close to the secret in spelling are left to the model, as the
+/// translations the model accepts are often cognates of the secret.
+#[derive(Clone, Debug, Default)]
+pub struct GuessMatcher {
+    /// Maps the normalized form of each word in the synonym table to the index of its group
+    synonym_groups: HashMap<String, usize>,
+
+    /// The stems of the guesses the judges deemed incorrect for the current secret
+    rejected_guesses: HashSet<String>,
+}
+
+impl GuessMatcher {
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `synonyms` - The synonym table, mapping a word to the list of words that should be
+    ///   accepted in its place. Relations are symmetric and transitive, that is, each entry
+    ///   and its synonyms form a single group of equivalent words.
+    pub fn new(synonyms: &HashMap<String, Vec<String>>) -> Self {
+        let mut synonym_groups: HashMap<String, usize> = HashMap::new();
+        let mut num_groups = 0;
+        for (word, words) in synonyms {
+            let keys = std::iter::once(word)
+                .chain(words)
+                .map(|word| normalize(word))
+                .filter(|key| !key.is_empty())
+                .collect::<Vec<_>>();
+            // Merge with any group that already contains one of the words
+            let group = keys
+                .iter()
+                .find_map(|key| synonym_groups.get(key).copied())
+                .unwrap_or_else(|| {
+                    num_groups += 1;
+                    num_groups - 1
+                });
+            let merged = keys
+                .iter()
+                .filter_map(|key| synonym_groups.get(key).copied())
+                .filter(|other| *other != group)
+                .collect::<Vec<_>>();
+            for value in synonym_groups.values_mut() {
+                if merged.contains(value) {
+                    *value = group;
+                }
+            }
+            for key in keys {
+                synonym_groups.insert(key, group);
+            }
+        }
+        Self {
+            synonym_groups,
+            rejected_guesses: HashSet::new(),
+        }
+    }
+
+    /// Records a guess the judges deemed incorrect for the current secret, so that the
+    /// same guess is settled without the model if it is submitted again.
+    pub fn record_miss(&mut self, guess: &str) {
+        let normalized_guess = normalize(guess);
+        if !normalized_guess.is_empty() {
+            self.rejected_guesses.insert(stem(&normalized_guess));
+        }
+    }
+
+    /// Forgets the guesses deemed incorrect, once the secret changes.
+    pub fn reset_misses(&mut self) {
+        self.rejected_guesses.clear();
+    }
+
+    /// Matches a guess against the secret.
+    ///
+    /// Returns `MatchOutcome::Ambiguous` whenever the matcher is not confident,
+    /// in which case the guess must be judged by the model.
+    ///
+    /// A guess sharing the secret's stem (e.g. its plural) is deliberately deferred
+    /// rather than accepted: a wrong `Correct` pays out the treasury pool, while a
+    /// deferral only costs a model call. Suffix rules cannot tell `glasses` (eyewear)
+    /// from `glass`, or `news` from `new`, so only the model can settle those.
+    pub fn check(&self, guess: &str, secret: &str) -> MatchOutcome {
+        let normalized_guess = normalize(guess);
+        if normalized_guess.is_empty() {
+            return MatchOutcome::Incorrect("The guess contains no letters or digits".to_string());
+        }
+        let normalized_secret = normalize(secret);
+        if normalized_guess == normalized_secret {
+            return MatchOutcome::Correct(
+                "The guess is the same as the secret, up to case, punctuation and whitespace"
+                    .to_string(),
+            );
+        }
+
+        if let (Some(guess_group), Some(secret_group)) = (
+            self.synonym_groups.get(&normalized_guess),
+            self.synonym_groups.get(&normalized_secret),
+        ) {
+            if guess_group == secret_group {
+                return MatchOutcome::Correct(
+                    "The guess is a listed synonym of the secret".to_string(),
+                );
+            }
+        }
+
+        if !normalized_guess.chars().any(char::is_alphabetic)
+            && normalized_secret.chars().any(char::is_alphabetic)
+        {
+            return MatchOutcome::Incorrect("The guess contains no letters".to_string());
+        }
+        // The singular or plural form of the secret is for the model to judge
+        let guess_key = stem(&normalized_guess);
+        if guess_key == stem(&normalized_secret) {
+            return MatchOutcome::Ambiguous;
+        }
+        if self.rejected_guesses.contains(&guess_key) {
+            return MatchOutcome::Incorrect(
+                "The judges already deemed the same guess incorrect for this secret".to_string(),
+            );
+        }
+        let guess_length = normalized_guess.chars().count();
+        let secret_length = normalized_secret.chars().count();
+        if guess_length > (secret_length * MAX_LENGTH_RATIO).max(secret_length + MIN_LENGTH_SLACK) {
+            return MatchOutcome::Incorrect(format!(
+                "The guess is {guess_length} characters long, far longer than the secret"
+            ));
+        }
+        MatchOutcome::Ambiguous
+    }
+}
+
+/// Normalizes a string for comparison, by applying Unicode NFKC normalization,
+/// case folding, and stripping any character that is not a letter or a digit.
+pub fn normalize(value: &str) -> String {
+    value
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Reduces a normalized English word to its singular form, using a small set of
+/// suffix rules. The same rules are applied to both sides of a comparison, so they
+/// only need to be consistent, not linguistically exact.
+pub fn stem(word: &str) -> String {
+    let num_chars = word.chars().count();
+    if num_chars <= 3 {
+        return word.to_string();
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        if num_chars > 4 {
+            return format!("{stem}y");
+        }
+    }
+    for suffix in ["sses", "shes", "ches", "xes", "zes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        return word.to_string();
+    }
+    word.strip_suffix('s').unwrap_or(word).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher() -> GuessMatcher {
+        GuessMatcher::new(&HashMap::from([(
+            "car".to_string(),
+            vec!["automobile".to_string(), "Motor car".to_string()],
+        )]))
+    }
+
+    #[test]
+    fn normalize_folds_case_width_punctuation_and_whitespace() {
+        assert_eq!(normalize("  Neil Armstrong! "), "neilarmstrong");
+        assert_eq!(normalize("ＥＩＦＦＥＬ"), "eiffel");
+        assert_eq!(normalize("Café-au-lait"), "caféaulait");
+        assert_eq!(normalize("?!. "), "");
+    }
+
+    #[test]
+    fn stem_reduces_plurals_to_their_singular() {
+        assert_eq!(stem("cats"), "cat");
+        assert_eq!(stem("berries"), "berry");
+        assert_eq!(stem("boxes"), "box");
+        assert_eq!(stem("churches"), "church");
+        assert_eq!(stem("glasses"), "glass");
+    }
+
+    #[test]
+    fn stem_keeps_words_that_are_not_plurals() {
+        assert_eq!(stem("bus"), "bus");
+        assert_eq!(stem("glass"), "glass");
+        assert_eq!(stem("virus"), "virus");
+        assert_eq!(stem("analysis"), "analysis");
+    }
+
+    #[test]
+    fn exact_match_up_to_normalization_is_correct() {
+        assert!(matches!(
+            matcher().check("  EIFFEL tower!", "Eiffel Tower"),
+            MatchOutcome::Correct(_)
+        ));
+    }
+
+    #[test]
+    fn listed_synonym_is_correct() {
+        let matcher = matcher();
+        assert!(matches!(
+            matcher.check("Automobile", "car"),
+            MatchOutcome::Correct(_)
+        ));
+        assert!(matches!(
+            matcher.check("motor-car", "automobile"),
+            MatchOutcome::Correct(_)
+        ));
+    }
+
+    #[test]
+    fn plural_of_the_secret_is_left_to_the_model() {
+        let matcher = matcher();
+        assert_eq!(matcher.check("cats", "cat"), MatchOutcome::Ambiguous);
+        assert_eq!(matcher.check("Cat", "cats"), MatchOutcome::Ambiguous);
+        assert_eq!(matcher.check("glasses", "glass"), MatchOutcome::Ambiguous);
+        // Nor is the plural of a synonym settled locally
+        assert_eq!(matcher.check("automobiles", "car"), MatchOutcome::Ambiguous);
+        // Different words sharing a stem are never deemed correct locally
+        assert_eq!(matcher.check("new", "news"), MatchOutcome::Ambiguous);
+    }
+
+    #[test]
+    fn guess_without_letters_is_incorrect() {
+        assert!(matches!(
+            matcher().check("!!!", "cat"),
+            MatchOutcome::Incorrect(_)
+        ));
+        assert!(matches!(
+            matcher().check("1234", "cat"),
+            MatchOutcome::Incorrect(_)
+        ));
+    }
+
+    #[test]
+    fn far_longer_guess_is_incorrect() {
+        let long_guess = "a small domesticated carnivorous mammal with soft fur";
+        assert!(matches!(
+            matcher().check(long_guess, "cat"),
+            MatchOutcome::Incorrect(_)
+        ));
+        // A short translation or phrasing of a short secret is left to the model
+        assert_eq!(
+            matcher().check("chat domestique", "cat"),
+            MatchOutcome::Ambiguous
+        );
+    }
+
+    #[test]
+    fn guesses_close_in_spelling_are_left_to_the_model() {
+        assert_eq!(matcher().check("rosa", "rose"), MatchOutcome::Ambiguous);
+    }
+
+    #[test]
+    fn recorded_misses_are_incorrect_until_reset() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.check("dog", "cat"), MatchOutcome::Ambiguous);
+        matcher.record_miss("Dog!");
+        assert!(matches!(
+            matcher.check("dogs", "cat"),
+            MatchOutcome::Incorrect(_)
+        ));
+        matcher.reset_misses();
+        assert_eq!(matcher.check("dog", "cat"), MatchOutcome::Ambiguous);
+    }
+
+    #[test]
+    fn recorded_miss_sharing_the_secret_stem_is_left_to_the_model() {
+        let mut matcher = matcher();
+        matcher.record_miss("new");
+        assert_eq!(
+            matcher.check("news", "news"),
+            MatchOutcome::Correct(
+                "The guess is the same as the secret, up to case, punctuation and whitespace"
+                    .to_string()
+            )
+        );
+        assert_eq!(matcher.check("new", "news"), MatchOutcome::Ambiguous);
+    }
+}
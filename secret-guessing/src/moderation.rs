@@ -0,0 +1,106 @@
+use crate::announcer::Announcement;
+
+/// Terms that never belong in a game announcement, as they are the hallmarks of
+/// scams targeting players. They are matched case-insensitively.
+const DEFAULT_BLOCKED_TERMS: &[&str] = &[
+    "airdrop",
+    "giveaway",
+    "free sui",
+    "dm me",
+    "send me",
+    "seed phrase",
+    "recovery phrase",
+    "private key",
+    "connect your wallet",
+    "verify your wallet",
+    "claim your",
+];
+
+/// Checks a model-authored post before it is handed over to an announcement channel.
+///
+/// A post is rejected if it is empty or longer than `max_length` characters, contains a
+/// control character (other than a line break) or a blocked term, drops one of the facts
+/// it must carry verbatim (the winner's address and payout transaction, or the hint), or
+/// mentions an address that is not part of the facts, which the model made up.
+///
+/// # Arguments
+///
+/// * `post` - The model-authored post
+/// * `announcement` - The announcement the post was authored from
+/// * `max_length` - Maximum length of the post, in characters
+/// * `blocked_terms` - Additional blocked terms, on top of the default ones
+///
+/// # Returns
+///
+/// `Some(reason)` if the post is rejected, `None` if it can be published.
+pub fn moderate_post(
+    post: &str,
+    announcement: &Announcement,
+    max_length: usize,
+    blocked_terms: &[String],
+) -> Option<String> {
+    if post.trim().is_empty() {
+        return Some("The post is empty".to_string());
+    }
+    let num_chars = post.chars().count();
+    if num_chars > max_length {
+        return Some(format!(
+            "The post is {num_chars} characters long, above the limit of {max_length}"
+        ));
+    }
+    if let Some(c) = post.chars().find(|c| c.is_control() && *c != '\n') {
+        return Some(format!("The post contains the control character {c:?}"));
+    }
+
+    let lowercase = post.to_lowercase();
+    if let Some(term) = DEFAULT_BLOCKED_TERMS
+        .iter()
+        .map(|term| term.to_string())
+        .chain(blocked_terms.iter().map(|term| term.to_lowercase()))
+        .find(|term| lowercase.contains(term.as_str()))
+    {
+        return Some(format!("The post contains the blocked term {term:?}"));
+    }
+
+    let (required_facts, addresses) = match announcement {
+        Announcement::Winner {
+            sender, tx_hash, ..
+        } => {
+            let sender = sender.to_string();
+            (vec![sender.clone(), tx_hash.clone()], vec![sender])
+        }
+        Announcement::Hint { hint, .. } => (vec![hint.clone()], vec![]),
+    };
+    if let Some(fact) = required_facts
+        .iter()
+        .find(|fact| !post.contains(fact.as_str()))
+    {
+        return Some(format!(
+            "The post does not contain the fact {fact:?} verbatim"
+        ));
+    }
+    if let Some(address) = post
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| word.starts_with("0x"))
+        .find(|word| !addresses.iter().any(|address| address == word))
+    {
+        return Some(format!(
+            "The post mentions the address {address:?}, which is not part of the facts"
+        ));
+    }
+    None
+}
+
+/// Truncates a post to at most `max_length` characters, ending it with an ellipsis
+/// if anything was cut.
+pub fn truncate_post(post: &str, max_length: usize) -> String {
+    if post.chars().count() <= max_length {
+        return post.to_string();
+    }
+    let mut truncated = post
+        .chars()
+        .take(max_length.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,469 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::{base_types::SuiAddress, digests::TransactionDigest, event::EventID};
+use thiserror::Error;
+use tracing::{info, instrument};
+
+use crate::{
+    config::SecretGuessingConfig,
+    dead_letter::entry_id,
+    store::{self, JsonStore, StoreError},
+};
+
+/// The default file path for the payout journal
+const DEFAULT_PAYOUT_JOURNAL_PATH: &str = "payout_journal.json";
+
+/// The number of basis points in a whole
+const MAX_BPS: u32 = 10_000;
+
+type Result<T> = std::result::Result<T, PayoutJournalError>;
+
+/// How the treasury pool is paid out to the winners of a round.
+///
+/// A share of the pool is paid out, the rest rolling over to the next round. A house
+/// fee is taken from the payout, and the remainder is split evenly among the winners,
+/// the first winner receiving the rounding remainder.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutPolicy {
+    /// Share of the treasury pool paid out on a win, in basis points
+    pub payout_bps: u32,
+
+    /// Share of the payout kept as a house fee, in basis points
+    pub house_fee_bps: u32,
+
+    /// Address receiving the house fee
+    pub house_address: Option<SuiAddress>,
+
+    /// Whether the correct guesses of the same checkpoint split the payout
+    pub split_same_checkpoint: bool,
+}
+
+impl Default for PayoutPolicy {
+    fn default() -> Self {
+        Self {
+            payout_bps: MAX_BPS,
+            house_fee_bps: 0,
+            house_address: None,
+            split_same_checkpoint: false,
+        }
+    }
+}
+
+/// The amounts a payout sends out of the treasury pool.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutPlan {
+    /// The recipients of the payout, winners first, along with their amount in MIST
+    pub recipients: Vec<(SuiAddress, u64)>,
+
+    /// The amount kept as a house fee, in MIST
+    pub house_fee: u64,
+
+    /// The amount left in the treasury pool for the next round, in MIST
+    pub rollover: u64,
+}
+
+impl PayoutPolicy {
+    /// Creates the payout policy from the application configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PayoutPolicyError` if a share exceeds the whole, or if a house fee is
+    /// configured without a valid house address.
+    pub fn from_config(
+        config: &SecretGuessingConfig,
+    ) -> std::result::Result<Self, PayoutPolicyError> {
+        let Some(config) = &config.payout_policy else {
+            return Ok(Self::default());
+        };
+        let payout_bps = config.payout_bps.unwrap_or(MAX_BPS);
+        let house_fee_bps = config.house_fee_bps.unwrap_or(0);
+        if payout_bps > MAX_BPS || house_fee_bps > MAX_BPS {
+            return Err(PayoutPolicyError::InvalidShare);
+        }
+        let house_address = config
+            .house_address
+            .as_deref()
+            .map(|address| {
+                SuiAddress::from_str(address)
+                    .map_err(|e| PayoutPolicyError::InvalidHouseAddress(e.to_string()))
+            })
+            .transpose()?;
+        if house_fee_bps > 0 && house_address.is_none() {
+            return Err(PayoutPolicyError::MissingHouseAddress);
+        }
+        Ok(Self {
+            payout_bps,
+            house_fee_bps,
+            house_address,
+            split_same_checkpoint: config.split_same_checkpoint.unwrap_or(false),
+        })
+    }
+
+    /// Whether a single winner is paid the whole treasury pool, in which case the pool
+    /// is withdrawn as a whole instead of by amount.
+    pub fn is_full_pool(&self) -> bool {
+        self.payout_bps == MAX_BPS && self.house_fee_bps == 0
+    }
+
+    /// Computes the amounts paid out of the treasury pool to the given winners.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool_balance` - The balance of the treasury pool, in MIST
+    /// * `winners` - The winners, in the order of their guesses. Duplicates get a single share
+    pub fn plan(&self, pool_balance: u64, winners: &[SuiAddress]) -> PayoutPlan {
+        let mut unique_winners: Vec<SuiAddress> = Vec::with_capacity(winners.len());
+        for winner in winners {
+            if !unique_winners.contains(winner) {
+                unique_winners.push(*winner);
+            }
+        }
+        let payout = bps_of(pool_balance, self.payout_bps);
+        let house_fee = match self.house_address {
+            Some(_) => bps_of(payout, self.house_fee_bps),
+            None => 0,
+        };
+        let winnings = payout - house_fee;
+
+        let mut recipients = Vec::with_capacity(unique_winners.len() + 1);
+        if let Some(num_winners) = u64::try_from(unique_winners.len()).ok().filter(|n| *n > 0) {
+            let share = winnings / num_winners;
+            let remainder = winnings % num_winners;
+            for (i, winner) in unique_winners.into_iter().enumerate() {
+                recipients.push((winner, if i == 0 { share + remainder } else { share }));
+            }
+        }
+        if let Some(house_address) = self.house_address.filter(|_| house_fee > 0) {
+            recipients.push((house_address, house_fee));
+        }
+        PayoutPlan {
+            recipients,
+            house_fee,
+            rollover: pool_balance - payout,
+        }
+    }
+}
+
+/// Returns the given share of an amount, in basis points, rounded down.
+fn bps_of(amount: u64, bps: u32) -> u64 {
+    (u128::from(amount) * u128::from(bps.min(MAX_BPS)) / u128::from(MAX_BPS)) as u64
+}
+
+/// The status of the payout of a winning guess.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayoutStatus {
+    /// The payout is due, and may have been sent without its outcome being recorded
+    Pending,
+
+    /// The winner was paid
+    Paid {
+        /// The digest of the payout transaction
+        tx_hash: String,
+
+        /// The amount of SUI (in MIST) the winner was paid
+        amount_paid: i128,
+    },
+}
+
+/// The payout of a winning guess, keyed by the guess event.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PayoutEntry {
+    /// The identifier of the entry, in the form `<tx_digest>:<event_seq>`
+    pub id: String,
+
+    /// The on-chain identifier of the winning guess event
+    pub event_id: EventID,
+
+    /// The address of the winner
+    pub winner: SuiAddress,
+
+    /// The winners of the same checkpoint sharing the payout, if the policy splits it
+    #[serde(default)]
+    pub co_winners: Vec<SuiAddress>,
+
+    /// The winning guess
+    pub guess: String,
+
+    /// The treasury pool balance reported by the guess event, in MIST
+    pub treasury_pool_balance: u64,
+
+    /// The status of the payout
+    pub status: PayoutStatus,
+
+    /// The number of payout transactions sent so far
+    pub attempts: u32,
+
+    /// Unix timestamp (in seconds) at which the first payout transaction was sent
+    pub first_attempt_at: Option<u64>,
+
+    /// The digests of the payout transactions sent so far, in order
+    #[serde(default)]
+    pub tx_digests: Vec<TransactionDigest>,
+
+    /// Unix timestamp (in seconds) of the last change to the entry
+    pub updated_at: u64,
+}
+
+/// A write-ahead journal of the payouts to winners.
+///
+/// The digest of each payout transaction is recorded before it is sent, and the outcome
+/// once it is executed. If the agent crashes in between, the guess is judged again when
+/// its event is replayed, and the engine looks up the journaled digests on-chain before
+/// sending another transaction, so that each winner is paid exactly once.
+pub struct PayoutJournal {
+    /// The payouts, keyed by their identifier
+    entries: JsonStore<PayoutEntry>,
+}
+
+impl PayoutJournal {
+    /// Constructor
+    pub fn new(path: String) -> Self {
+        Self {
+            entries: JsonStore::new(path),
+        }
+    }
+
+    /// Creates the payout journal from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        Self::new(
+            config
+                .payout_journal_path
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PAYOUT_JOURNAL_PATH.to_string()),
+        )
+    }
+
+    /// Returns all the payouts, ordered by their identifier.
+    pub fn list(&self) -> Result<Vec<PayoutEntry>> {
+        Ok(self.entries.values()?)
+    }
+
+    /// Returns the payouts that may have been sent without their outcome being recorded.
+    pub fn unconfirmed(&self) -> Result<Vec<PayoutEntry>> {
+        Ok(self
+            .entries
+            .values()?
+            .into_iter()
+            .filter(|entry| entry.status == PayoutStatus::Pending && entry.attempts > 0)
+            .collect())
+    }
+
+    /// Whether the winner of a guess was paid.
+    pub fn is_paid(&self, event_id: &EventID) -> Result<bool> {
+        Ok(self
+            .entries
+            .read()?
+            .get(&entry_id(event_id))
+            .is_some_and(|entry| matches!(entry.status, PayoutStatus::Paid { .. })))
+    }
+
+    /// Returns the payout of a winning guess, recording it as pending if it is new.
+    pub fn get_or_insert(
+        &self,
+        event_id: EventID,
+        winner: SuiAddress,
+        co_winners: Vec<SuiAddress>,
+        guess: String,
+        treasury_pool_balance: u64,
+    ) -> Result<PayoutEntry> {
+        let id = entry_id(&event_id);
+        self.entries.update(|entries| {
+            let entry = entries.entry(id.clone()).or_insert_with(|| PayoutEntry {
+                id,
+                event_id,
+                winner,
+                co_winners,
+                guess,
+                treasury_pool_balance,
+                status: PayoutStatus::Pending,
+                attempts: 0,
+                first_attempt_at: None,
+                tx_digests: Vec::new(),
+                updated_at: store::unix_timestamp_secs(),
+            });
+            Ok(entry.clone())
+        })
+    }
+
+    /// Records that a payout transaction is about to be sent. Must be called before
+    /// sending it, so that a crash while it is in flight leaves its digest to look up.
+    pub fn record_attempt(&self, event_id: &EventID, tx_digest: TransactionDigest) -> Result<()> {
+        let now = store::unix_timestamp_secs();
+        self.update(event_id, |entry| {
+            entry.attempts += 1;
+            entry.first_attempt_at.get_or_insert(now);
+            entry.tx_digests.push(tx_digest);
+        })
+    }
+
+    /// Records that the winner of a guess was paid.
+    #[instrument(level = "info", skip_all, fields(tx_hash = %tx_hash))]
+    pub fn record_paid(
+        &self,
+        event_id: &EventID,
+        tx_hash: String,
+        amount_paid: i128,
+    ) -> Result<()> {
+        self.update(event_id, |entry| {
+            entry.status = PayoutStatus::Paid {
+                tx_hash: tx_hash.clone(),
+                amount_paid,
+            };
+        })?;
+        info!(
+            target = "payout-journal",
+            id = %entry_id(event_id),
+            amount_paid = %amount_paid,
+            "Recorded the payout of a winning guess"
+        );
+        Ok(())
+    }
+
+    fn update(&self, event_id: &EventID, f: impl FnOnce(&mut PayoutEntry)) -> Result<()> {
+        let id = entry_id(event_id);
+        self.entries.update(|entries| {
+            let entry = entries
+                .get_mut(&id)
+                .ok_or(PayoutJournalError::UnknownPayout(id))?;
+            f(entry);
+            entry.updated_at = store::unix_timestamp_secs();
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PayoutPolicyError {
+    #[error("Payout shares must be at most {MAX_BPS} basis points")]
+    InvalidShare,
+    #[error("A house fee requires a house address")]
+    MissingHouseAddress,
+    #[error("Invalid house address: {0}")]
+    InvalidHouseAddress(String),
+}
+
+#[derive(Debug, Error)]
+pub enum PayoutJournalError {
+    #[error("Payout journal store error: {0}")]
+    StoreError(#[from] StoreError),
+    #[error("No payout recorded with id `{0}`")]
+    UnknownPayout(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(
+        payout_bps: u32,
+        house_fee_bps: u32,
+        house_address: Option<SuiAddress>,
+    ) -> PayoutPolicy {
+        PayoutPolicy {
+            payout_bps,
+            house_fee_bps,
+            house_address,
+            split_same_checkpoint: true,
+        }
+    }
+
+    fn total(plan: &PayoutPlan) -> u64 {
+        plan.recipients
+            .iter()
+            .map(|(_, amount)| amount)
+            .sum::<u64>()
+            + plan.rollover
+    }
+
+    #[test]
+    fn default_policy_pays_the_whole_pool_to_the_winner() {
+        let winner = SuiAddress::random_for_testing_only();
+        let policy = PayoutPolicy::default();
+        assert!(policy.is_full_pool());
+        let plan = policy.plan(1_000, &[winner]);
+        assert_eq!(plan.recipients, vec![(winner, 1_000)]);
+        assert_eq!(plan.house_fee, 0);
+        assert_eq!(plan.rollover, 0);
+    }
+
+    #[test]
+    fn unpaid_share_rolls_over() {
+        let winner = SuiAddress::random_for_testing_only();
+        let policy = policy(6_000, 0, None);
+        assert!(!policy.is_full_pool());
+        // 60% of 1001 is rounded down, the rounding staying in the pool
+        let plan = policy.plan(1_001, &[winner]);
+        assert_eq!(plan.recipients, vec![(winner, 600)]);
+        assert_eq!(plan.rollover, 401);
+        assert_eq!(total(&plan), 1_001);
+    }
+
+    #[test]
+    fn house_fee_is_taken_from_the_payout() {
+        let winner = SuiAddress::random_for_testing_only();
+        let house = SuiAddress::random_for_testing_only();
+        let plan = policy(5_000, 1_000, Some(house)).plan(1_000_000, &[winner]);
+        assert_eq!(plan.recipients, vec![(winner, 450_000), (house, 50_000)]);
+        assert_eq!(plan.house_fee, 50_000);
+        assert_eq!(plan.rollover, 500_000);
+        assert_eq!(total(&plan), 1_000_000);
+    }
+
+    #[test]
+    fn house_fee_is_waived_without_a_house_address() {
+        let winner = SuiAddress::random_for_testing_only();
+        let plan = policy(MAX_BPS, 1_000, None).plan(1_000, &[winner]);
+        assert_eq!(plan.recipients, vec![(winner, 1_000)]);
+        assert_eq!(plan.house_fee, 0);
+    }
+
+    #[test]
+    fn split_gives_the_rounding_remainder_to_the_first_winner() {
+        let winners = [
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+        ];
+        let plan = policy(MAX_BPS, 0, None).plan(100, &winners);
+        assert_eq!(
+            plan.recipients,
+            vec![(winners[0], 34), (winners[1], 33), (winners[2], 33)]
+        );
+        assert_eq!(total(&plan), 100);
+    }
+
+    #[test]
+    fn split_gives_a_single_share_to_duplicate_winners() {
+        let first = SuiAddress::random_for_testing_only();
+        let second = SuiAddress::random_for_testing_only();
+        let plan = policy(MAX_BPS, 0, None).plan(101, &[first, second, first]);
+        assert_eq!(plan.recipients, vec![(first, 51), (second, 50)]);
+    }
+
+    #[test]
+    fn split_with_rollover_and_house_fee_pays_out_the_whole_pool() {
+        let winners = [
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+        ];
+        let house = SuiAddress::random_for_testing_only();
+        for pool_balance in [0, 1, 7, 999, 1_000_003, u64::MAX] {
+            let plan = policy(7_500, 333, Some(house)).plan(pool_balance, &winners);
+            assert_eq!(total(&plan), pool_balance);
+            let shares = plan.recipients[1].1;
+            assert!(plan.recipients[..3]
+                .iter()
+                .all(|(_, amount)| *amount >= shares && *amount - shares < 3));
+        }
+    }
+
+    #[test]
+    fn bps_of_rounds_down_and_caps_the_share() {
+        assert_eq!(bps_of(999, 5_000), 499);
+        assert_eq!(bps_of(u64::MAX, MAX_BPS), u64::MAX);
+        assert_eq!(bps_of(100, MAX_BPS + 1), 100);
+        assert_eq!(bps_of(100, 0), 0);
+    }
+}
@@ -0,0 +1,296 @@
+use std::{str::FromStr, time::Duration};
+
+use sui_sdk::{
+    rpc_types::{EventFilter, SuiEvent},
+    types::{
+        base_types::{ObjectID, ObjectIDParseError},
+        digests::TransactionDigest,
+        event::EventID,
+        Identifier,
+    },
+    wallet_context::WalletContext,
+};
+use thiserror::Error;
+use tracing::{info, instrument};
+
+use crate::{
+    client::{GameState, SuiClientContext, SuiClientError, TxOutcome},
+    config::SecretGuessingConfig,
+    engine::events::{NewGuessEvent, SecretRevealedEvent},
+    gas::GasManager,
+    transactions::SecretGuessingCall,
+    SECRET_GUESSING_MODULE_NAME,
+};
+
+type Result<T> = std::result::Result<T, PlayerClientError>;
+
+/// The interval between two polls for the outcome of a guess
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The default number of events fetched per query
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+/// A guess submitted on-chain, as recorded by its `NewGuessEvent`.
+#[derive(Clone, Debug)]
+pub struct SubmittedGuess {
+    /// The digest of the transaction submitting the guess
+    pub digest: TransactionDigest,
+
+    /// The ID of the guess event, from which the outcome is watched
+    pub event_id: EventID,
+
+    /// The guess event
+    pub event: NewGuessEvent,
+
+    /// The outcome of the transaction submitting the guess
+    pub outcome: TxOutcome,
+}
+
+/// The outcome of a guess, once the secret of its round is revealed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GuessOutcome {
+    /// The player won the treasury pool
+    Won {
+        /// The revealed secret
+        secret: String,
+
+        /// The amount of SUI (in MIST) the player was paid
+        amount_paid: i128,
+
+        /// The digest of the payout transaction
+        tx_hash: String,
+    },
+
+    /// The round ended without paying the player
+    Lost {
+        /// The revealed secret
+        secret: String,
+    },
+}
+
+/// A client for the players of the Secret Guessing game.
+///
+/// It submits guesses from the player's wallet, paying the fee with a coin split from
+/// the gas coin, reads the state of the game, and watches the player's guesses until
+/// the secret of their round is revealed.
+pub struct PlayerClient {
+    /// The Sui client context of the player's wallet
+    sui_client_ctx: SuiClientContext,
+
+    /// Number of events fetched per query
+    limit: usize,
+
+    /// Interval between two polls for the outcome of a guess
+    poll_interval: Duration,
+}
+
+impl PlayerClient {
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The application configuration, pointing to the Secret Guessing package
+    /// * `secret_guessing_db` - The ID of the Secret Guessing database object
+    /// * `wallet_context` - The player's wallet
+    ///
+    /// # Errors
+    ///
+    /// Returns `PlayerClientError::ParseObjectIDError` if the configured package ID is invalid.
+    pub fn new(
+        config: &SecretGuessingConfig,
+        secret_guessing_db: ObjectID,
+        wallet_context: WalletContext,
+    ) -> Result<Self> {
+        let package_id = ObjectID::from_str(&config.package_id)?;
+        let sui_client_ctx = SuiClientContext::new(secret_guessing_db, package_id, wallet_context)
+            .with_gas_manager(GasManager::from_config(config));
+        Ok(Self {
+            sui_client_ctx,
+            limit: config.limit.unwrap_or(DEFAULT_QUERY_LIMIT),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        })
+    }
+
+    /// Sets the interval between two polls for the outcome of a guess.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Reads the state of the game, e.g. the current fee and treasury pool balance.
+    pub async fn game_state(&self) -> Result<GameState> {
+        Ok(self.sui_client_ctx.fetch_game_state().await?)
+    }
+
+    /// Submits a guess, paying the current fee out of the player's wallet.
+    ///
+    /// # Arguments
+    ///
+    /// * `guess` - The guess
+    ///
+    /// # Returns
+    ///
+    /// The submitted guess, along with the `NewGuessEvent` it emitted.
+    ///
+    /// # Errors
+    ///
+    /// * `PlayerClientError::SuiClientError` - If the transaction fails, e.g. when the game is paused
+    /// * `PlayerClientError::MissingGuessEvent` - If the transaction emitted no `NewGuessEvent`
+    #[instrument(level = "info", skip_all, fields(guess = %guess))]
+    pub async fn submit_guess(&mut self, guess: &str) -> Result<SubmittedGuess> {
+        let fee = self.game_state().await?.fee;
+        let outcome = self
+            .sui_client_ctx
+            .execute_calls(
+                vec![SecretGuessingCall::SubmitGuess {
+                    guess: guess.to_string(),
+                    fee,
+                }],
+                None,
+                None,
+                None,
+            )
+            .await?;
+        let digest = TransactionDigest::from_str(&outcome.digest)?;
+        let (event_id, event) = outcome
+            .events
+            .iter()
+            .find_map(|event| self.parse_guess_event(event))
+            .ok_or_else(|| PlayerClientError::MissingGuessEvent(outcome.digest.clone()))?;
+
+        info!(
+            target = "player-client",
+            fee,
+            guess_count = event.guess_count,
+            treasury_pool_balance = event.treasury_pool_balance,
+            "Submitted guess, tx_hash: {digest}"
+        );
+
+        Ok(SubmittedGuess {
+            digest,
+            event_id,
+            event,
+            outcome,
+        })
+    }
+
+    /// Fetches the player's own guesses, in the order they were made.
+    ///
+    /// # Arguments
+    ///
+    /// * `cursor` - The ID of the last guess event already seen, if any
+    ///
+    /// # Returns
+    ///
+    /// The guesses made after the cursor, along with the cursor to resume from.
+    pub async fn own_guesses(
+        &mut self,
+        cursor: Option<EventID>,
+    ) -> Result<(Vec<(EventID, NewGuessEvent)>, Option<EventID>)> {
+        let player = self.sui_client_ctx.active_address()?;
+        let client = self.sui_client_ctx.sui_client().await?;
+        let page = client
+            .event_api()
+            .query_events(EventFilter::Sender(player), cursor, Some(self.limit), false)
+            .await?;
+        let guesses = page
+            .data
+            .iter()
+            .filter_map(|event| self.parse_guess_event(event))
+            .collect();
+        Ok((guesses, page.next_cursor.or(cursor)))
+    }
+
+    /// Waits until the secret of a guess's round is revealed, and tells whether the
+    /// player won.
+    ///
+    /// The player won if a treasury payout credited their address between the guess and
+    /// the reveal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the events or transactions cannot be queried.
+    #[instrument(level = "info", skip_all, fields(digest = %guess.digest))]
+    pub async fn wait_for_outcome(&mut self, guess: &SubmittedGuess) -> Result<GuessOutcome> {
+        let player = self.sui_client_ctx.active_address()?;
+        let client = self.sui_client_ctx.sui_client().await?;
+        let filter = EventFilter::MoveModule {
+            package: self.sui_client_ctx.package_id(),
+            module: Identifier::new(SECRET_GUESSING_MODULE_NAME)?,
+        };
+        // The timestamp of the guess is only known once its checkpoint is indexed
+        let guessed_at_ms = client
+            .event_api()
+            .query_events(EventFilter::Transaction(guess.digest), None, Some(1), false)
+            .await?
+            .data
+            .first()
+            .and_then(|event| event.timestamp_ms)
+            .unwrap_or_default();
+
+        let mut cursor = Some(guess.event_id);
+        loop {
+            let page = client
+                .event_api()
+                .query_events(filter.clone(), cursor, Some(self.limit), false)
+                .await?;
+            for event in &page.data {
+                if event.type_.name.as_str() != "SecretRevealedEvent" {
+                    continue;
+                }
+                let revealed: SecretRevealedEvent =
+                    serde_json::from_value(event.parsed_json.clone())?;
+                let secret = String::from_utf8_lossy(&revealed.secret).into_owned();
+                let revealed_at_ms = event.timestamp_ms.unwrap_or(u64::MAX);
+                return Ok(
+                    match self
+                        .sui_client_ctx
+                        .find_treasury_payout(player, guessed_at_ms, revealed_at_ms)
+                        .await?
+                    {
+                        Some(payout) => GuessOutcome::Won {
+                            secret,
+                            amount_paid: payout.sui_received_by(player),
+                            tx_hash: payout.digest,
+                        },
+                        None => GuessOutcome::Lost { secret },
+                    },
+                );
+            }
+            if page.next_cursor.is_some() {
+                cursor = page.next_cursor;
+            }
+            if !page.has_next_page {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    /// Parses a `NewGuessEvent` of the Secret Guessing package.
+    fn parse_guess_event(&self, event: &SuiEvent) -> Option<(EventID, NewGuessEvent)> {
+        if event.package_id != self.sui_client_ctx.package_id()
+            || event.type_.module.as_str() != SECRET_GUESSING_MODULE_NAME
+            || event.type_.name.as_str() != "NewGuessEvent"
+        {
+            return None;
+        }
+        let guess = serde_json::from_value(event.parsed_json.clone()).ok()?;
+        Some((event.id, guess))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PlayerClientError {
+    #[error("Sui client error: {0}")]
+    SuiClientError(#[from] SuiClientError),
+    #[error("Failed to query the Sui RPC node: {0}")]
+    RpcError(#[from] sui_sdk::error::Error),
+    #[error("Failed to parse object ID: {0}")]
+    ParseObjectIDError(#[from] ObjectIDParseError),
+    #[error("Failed to parse: {0}")]
+    ParseError(#[from] anyhow::Error),
+    #[error("Failed to parse event: {0}")]
+    InvalidEvent(#[from] serde_json::Error),
+    #[error("Transaction {0} emitted no `NewGuessEvent`")]
+    MissingGuessEvent(String),
+}
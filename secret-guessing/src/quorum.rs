@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::{JudgeConfig, SecretGuessingConfig};
+
+type Result<T> = std::result::Result<T, QuorumError>;
+
+/// The vote of a single judge on a guess.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JudgeVote {
+    /// The model that cast the vote
+    pub model: String,
+
+    /// The seed used for the inference request
+    pub seed: u64,
+
+    /// Whether the judge deemed the guess correct, after verification
+    pub is_correct: bool,
+
+    /// Whether the judge's verdict passed the extracted-guess verification. A correct
+    /// verdict that fails it is counted as an incorrect vote
+    pub verified: bool,
+
+    /// The judge's explanation of its verdict
+    pub explanation: String,
+
+    /// The node's signature of the judge's response (base64 encoded), if it signed it
+    #[serde(default)]
+    pub signature: Option<String>,
+
+    /// The hash of the judge's response (base64 encoded), as sent by the node
+    #[serde(default)]
+    pub response_hash: Option<String>,
+}
+
+impl std::fmt::Display for JudgeVote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "model={} seed={} is_correct={} verified={} explanation={}",
+            self.model, self.seed, self.is_correct, self.verified, self.explanation
+        )
+    }
+}
+
+/// The collective decision of the judges on a guess.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuorumDecision {
+    /// At least `threshold` judges deemed the guess correct
+    Correct,
+
+    /// No judge deemed the guess correct
+    Incorrect,
+
+    /// Some, but not enough, judges deemed the guess correct
+    Split,
+}
+
+/// A judging quorum: a set of judges, and the number of them that must agree
+/// that a guess is correct before the treasury is paid out.
+#[derive(Clone, Debug)]
+pub struct Quorum {
+    /// The judges, each of them queried with a separate inference request
+    pub judges: Vec<JudgeConfig>,
+
+    /// The number of correct votes needed for a payout
+    pub threshold: usize,
+}
+
+impl Quorum {
+    /// Constructor
+    ///
+    /// # Errors
+    ///
+    /// Returns `QuorumError::InvalidThreshold` if the threshold is zero, or above the
+    /// number of votes the judges cast (one per choice), as a guess could then never
+    /// reach the quorum and every correct guess would go to review.
+    pub fn new(judges: Vec<JudgeConfig>, threshold: usize) -> Result<Self> {
+        let num_votes = judges
+            .iter()
+            .map(|judge| judge.choices.unwrap_or(1).max(1) as usize)
+            .sum::<usize>();
+        if threshold == 0 || threshold > num_votes {
+            return Err(QuorumError::InvalidThreshold {
+                threshold,
+                num_votes,
+            });
+        }
+        Ok(Self { judges, threshold })
+    }
+
+    /// Creates the judging quorum from the application configuration.
+    ///
+    /// If no quorum is configured, a single judge using the configured model decides
+    /// on its own, which is the behavior of the engine without a quorum.
+    ///
+    /// # Errors
+    ///
+    /// Returns `QuorumError::InvalidThreshold` if the configured threshold cannot be reached.
+    pub fn from_config(config: &SecretGuessingConfig) -> Result<Self> {
+        match &config.judging_quorum {
+            Some(quorum) if !quorum.judges.is_empty() => {
+                Self::new(quorum.judges.clone(), quorum.threshold)
+            }
+            _ => Self::new(vec![JudgeConfig::default()], 1),
+        }
+    }
+
+    /// Tallies the votes of the judges.
+    pub fn tally(&self, votes: &[JudgeVote]) -> QuorumDecision {
+        let num_correct = votes.iter().filter(|vote| vote.is_correct).count();
+        if num_correct >= self.threshold {
+            QuorumDecision::Correct
+        } else if num_correct == 0 {
+            QuorumDecision::Incorrect
+        } else {
+            QuorumDecision::Split
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QuorumError {
+    #[error(
+        "Quorum threshold of {threshold} must be between 1 and the {num_votes} votes of the judges"
+    )]
+    InvalidThreshold { threshold: usize, num_votes: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn judge(choices: Option<u32>) -> JudgeConfig {
+        JudgeConfig {
+            choices,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn threshold_up_to_the_number_of_votes_is_accepted() {
+        let quorum = Quorum::new(vec![judge(None), judge(Some(3))], 4).unwrap();
+        assert_eq!(quorum.threshold, 4);
+        assert!(Quorum::new(vec![judge(Some(0))], 1).is_ok());
+    }
+
+    #[test]
+    fn unreachable_threshold_is_rejected() {
+        assert!(matches!(
+            Quorum::new(vec![judge(None), judge(Some(2))], 4),
+            Err(QuorumError::InvalidThreshold {
+                threshold: 4,
+                num_votes: 3
+            })
+        ));
+        assert!(matches!(
+            Quorum::new(vec![judge(None)], 0),
+            Err(QuorumError::InvalidThreshold { threshold: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::{base_types::SuiAddress, event::EventID};
+use thiserror::Error;
+use tracing::{info, instrument};
+
+use crate::{
+    config::SecretGuessingConfig,
+    dead_letter,
+    engine::events::SubmittedGuess,
+    quorum::JudgeVote,
+    store::{self, JsonStore, StoreError},
+};
+
+/// The default file path for the review queue
+const DEFAULT_REVIEW_QUEUE_PATH: &str = "review_queue.json";
+
+type Result<T> = std::result::Result<T, ReviewError>;
+
+/// A guess on which the judges could not reach a quorum, waiting for an operator.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReviewEntry {
+    /// The identifier of the entry, in the form `<tx_digest>:<event_seq>`
+    pub id: String,
+
+    /// The on-chain identifier of the `NewGuessEvent`
+    pub event_id: EventID,
+
+    /// The player who submitted the guess
+    pub sender: SuiAddress,
+
+    /// The guess itself
+    pub guess: String,
+
+    /// The guess count at the time of the guess
+    pub guess_count: u64,
+
+    /// The treasury pool balance at the time of the guess
+    pub treasury_pool_balance: u64,
+
+    /// The votes of all the judges
+    pub votes: Vec<JudgeVote>,
+
+    /// The round in which the guess was made
+    pub round: u64,
+
+    /// Unix timestamp (in seconds) at which the guess was escalated
+    pub queued_at: u64,
+
+    /// The operator's resolution of the guess, `None` while it waits for review
+    #[serde(default)]
+    pub resolution: Option<ReviewResolution>,
+}
+
+impl ReviewEntry {
+    /// The guess waiting for review.
+    pub fn submitted(&self) -> SubmittedGuess {
+        SubmittedGuess {
+            event_id: self.event_id,
+            sender: self.sender,
+            guess: self.guess.clone(),
+            guess_count: self.guess_count,
+            treasury_pool_balance: self.treasury_pool_balance,
+        }
+    }
+}
+
+impl std::fmt::Display for ReviewEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} sender={} guess={:?} guess_count={} treasury_pool_balance={} round={} queued_at={}",
+            self.id,
+            self.sender,
+            self.guess,
+            self.guess_count,
+            self.treasury_pool_balance,
+            self.round,
+            self.queued_at
+        )?;
+        if let Some(resolution) = self.resolution {
+            write!(
+                f,
+                "\n    resolution={resolution:?}, waiting for the running engine"
+            )?;
+        }
+        for vote in &self.votes {
+            write!(f, "\n    {vote}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An operator's resolution of a guess on which the judges were split.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewResolution {
+    /// The guess is correct, and is settled like any correct guess (i.e. paid out,
+    /// or queued for approval if the payout is above the approval threshold)
+    Correct,
+
+    /// The guess is incorrect
+    Incorrect,
+}
+
+/// A persistent queue of split decisions, escalated for an operator to review.
+pub struct ReviewQueue {
+    /// The guesses waiting for review, keyed by their identifier
+    entries: JsonStore<ReviewEntry>,
+}
+
+impl ReviewQueue {
+    /// Constructor
+    pub fn new(path: String) -> Self {
+        Self {
+            entries: JsonStore::new(path),
+        }
+    }
+
+    /// Creates the review queue from the application configuration.
+    pub fn from_config(config: &SecretGuessingConfig) -> Self {
+        Self::new(
+            config
+                .judging_quorum
+                .as_ref()
+                .and_then(|quorum| quorum.review_queue_path.clone())
+                .unwrap_or_else(|| DEFAULT_REVIEW_QUEUE_PATH.to_string()),
+        )
+    }
+
+    /// Returns all the guesses waiting for review.
+    pub fn list(&self) -> Result<Vec<ReviewEntry>> {
+        Ok(self.entries.values()?)
+    }
+
+    /// Returns the guesses an operator resolved, for the running engine to settle.
+    pub fn resolved(&self) -> Result<Vec<ReviewEntry>> {
+        Ok(self
+            .entries
+            .values()?
+            .into_iter()
+            .filter(|entry| entry.resolution.is_some())
+            .collect())
+    }
+
+    /// Escalates a guess for review, along with the votes of the judges.
+    #[instrument(level = "info", skip_all, fields(guess = %submitted.guess))]
+    pub fn escalate(
+        &self,
+        submitted: &SubmittedGuess,
+        votes: Vec<JudgeVote>,
+        round: u64,
+    ) -> Result<()> {
+        let id = dead_letter::entry_id(&submitted.event_id);
+        let entry = ReviewEntry {
+            id: id.clone(),
+            event_id: submitted.event_id,
+            sender: submitted.sender,
+            guess: submitted.guess.clone(),
+            guess_count: submitted.guess_count,
+            treasury_pool_balance: submitted.treasury_pool_balance,
+            votes,
+            round,
+            queued_at: store::unix_timestamp_secs(),
+            resolution: None,
+        };
+        self.entries.update(|entries| {
+            entries.insert(id.clone(), entry);
+            Ok::<_, ReviewError>(())
+        })?;
+        info!(
+            target = "review-queue",
+            id = %id,
+            "Guess escalated for review, the judges did not reach a quorum"
+        );
+        Ok(())
+    }
+
+    /// Resolves a guess waiting for review. The running engine then settles it through
+    /// the same flow as a guess the judges agreed on, and removes it from the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such guess, or if it was already resolved.
+    #[instrument(level = "info", skip(self))]
+    pub fn resolve(&self, id: &str, resolution: ReviewResolution) -> Result<ReviewEntry> {
+        let entry = self.entries.update(|entries| {
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| ReviewError::UnknownReview(id.to_string()))?;
+            if let Some(resolution) = entry.resolution {
+                return Err(ReviewError::AlreadyResolved(id.to_string(), resolution));
+            }
+            entry.resolution = Some(resolution);
+            Ok(entry.clone())
+        })?;
+        info!(
+            target = "review-queue",
+            id = %id,
+            "Guess resolved by an operator as {resolution:?}"
+        );
+        Ok(entry)
+    }
+
+    /// Removes a guess from the review queue, once an operator has dealt with it.
+    pub fn remove(&self, id: &str) -> Result<Option<ReviewEntry>> {
+        Ok(self.entries.remove(id)?)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReviewError {
+    #[error("Review queue store error: {0}")]
+    StoreError(#[from] StoreError),
+    #[error("No guess waiting for review with id `{0}`")]
+    UnknownReview(String),
+    #[error("Guess `{0}` was already resolved as {1:?}")]
+    AlreadyResolved(String, ReviewResolution),
+}
@@ -0,0 +1,485 @@
+use std::{path::Path, sync::OnceLock};
+
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
+use base64::engine::{general_purpose::STANDARD, Engine};
+use hkdf::Hkdf;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sui_sdk::types::event::EventID;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::{KeyProviderConfig, SecretCategory, SecretGuessingConfig},
+    payout::PayoutPolicy,
+    selection::SaltChain,
+    store::{self, StoreError},
+};
+
+/// The default file path of the sealed game state
+const DEFAULT_SEALED_STATE_PATH: &str = "sealed_state.json";
+
+/// The configfs-tsm directory under which TDX quotes are requested
+const TSM_REPORT_DIR: &str = "/sys/kernel/config/tsm/report";
+
+/// The size of the sealing key in bytes
+const SEALING_KEY_SIZE: usize = 32;
+
+/// The size of the nonce in bytes
+const NONCE_SIZE: usize = 12;
+
+/// The size of the report data of a TDX quote in bytes
+const TDX_REPORT_DATA_SIZE: usize = 64;
+
+/// The offset of `MRTD` in a version 4 TDX quote: a 48-byte header, followed by the TD
+/// report body's `TEE_TCB_SVN` (16), `MRSEAM` (48), `MRSIGNERSEAM` (48), `SEAMATTRIBUTES` (8),
+/// `TDATTRIBUTES` (8) and `XFAM` (8)
+const TDX_QUOTE_MRTD_OFFSET: usize = 184;
+
+/// The offset of `RTMR0` in a version 4 TDX quote, after `MRTD`, `MRCONFIGID`, `MROWNER`
+/// and `MROWNERCONFIG` (48 bytes each)
+const TDX_QUOTE_RTMR0_OFFSET: usize = 376;
+
+/// The size of a TDX measurement register in bytes
+const TDX_MEASUREMENT_SIZE: usize = 48;
+
+/// The number of runtime measurement registers the sealing key is bound to. `RTMR3` is
+/// left out, as it is extended by the workload at runtime
+const TDX_SEALED_RTMRS: usize = 3;
+
+/// The version of the sealed envelope format
+const SEALED_ENVELOPE_VERSION: u32 = 1;
+
+type Result<T> = std::result::Result<T, SealingError>;
+
+/// A source of the key used to seal the game state to disk.
+pub trait KeyProvider: Send + Sync {
+    /// The name of the key provider, recorded in the sealed envelope.
+    fn name(&self) -> &str;
+
+    /// Returns the sealing key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SealingError` if the key cannot be obtained.
+    fn sealing_key(&self) -> Result<[u8; SEALING_KEY_SIZE]>;
+}
+
+/// A key provider reading the sealing key from a local file, for development.
+///
+/// The key file is created with a random key if it does not exist. Anyone who can read
+/// it can unseal the game state, so this provider must not be used in production.
+pub struct FileKeyProvider {
+    /// File path of the sealing key
+    path: String,
+}
+
+impl FileKeyProvider {
+    /// Constructor
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn sealing_key(&self) -> Result<[u8; SEALING_KEY_SIZE]> {
+        let key = match std::fs::read(&self.path) {
+            Ok(key) => key,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = rand::thread_rng().gen::<[u8; SEALING_KEY_SIZE]>();
+                write_private_file(&self.path, &key)?;
+                warn!(
+                    target = "sealing",
+                    path = %self.path,
+                    "Created a new development sealing key, do not use the file key provider in production"
+                );
+                return Ok(key);
+            }
+            Err(e) => return Err(SealingError::IoError(e)),
+        };
+        key.try_into().map_err(|key: Vec<u8>| {
+            SealingError::InvalidKeyError(format!(
+                "Sealing key file must be {SEALING_KEY_SIZE} bytes long, it is {} bytes long",
+                key.len()
+            ))
+        })
+    }
+}
+
+/// A key provider deriving the sealing key inside an Intel TDX trust domain.
+///
+/// TDX has no hardware sealing key, so the key is derived from two parts: a root key
+/// released to the trust domain at boot (typically by a key broker, once the trust domain
+/// has been attested), and the trust domain's build and boot measurements (`MRTD` and
+/// `RTMR0` to `RTMR2`), read from a quote through configfs-tsm. A modified image
+/// measures differently, so it derives a different key even if it obtains the root key.
+///
+/// The key is derived once, on first use, and reused for every seal, as the state is
+/// sealed after each guess and neither part changes while the trust domain runs.
+pub struct TdxKeyProvider {
+    /// File path of the root key released to the trust domain
+    root_key_path: String,
+
+    /// The sealing key, once derived
+    sealing_key: OnceLock<[u8; SEALING_KEY_SIZE]>,
+}
+
+impl TdxKeyProvider {
+    /// Constructor
+    pub fn new(root_key_path: String) -> Self {
+        Self {
+            root_key_path,
+            sealing_key: OnceLock::new(),
+        }
+    }
+
+    /// Reads the measurements the sealing key is bound to, from a fresh TDX quote.
+    fn measurements(&self) -> Result<Vec<u8>> {
+        let quote = tdx_quote("sealing", &[])?;
+
+        let rtmrs_end = TDX_QUOTE_RTMR0_OFFSET + TDX_SEALED_RTMRS * TDX_MEASUREMENT_SIZE;
+        if quote.len() < rtmrs_end {
+            return Err(SealingError::InvalidKeyError(format!(
+                "TDX quote is too short: {} bytes",
+                quote.len()
+            )));
+        }
+        let mut measurements =
+            quote[TDX_QUOTE_MRTD_OFFSET..TDX_QUOTE_MRTD_OFFSET + TDX_MEASUREMENT_SIZE].to_vec();
+        measurements.extend_from_slice(&quote[TDX_QUOTE_RTMR0_OFFSET..rtmrs_end]);
+        Ok(measurements)
+    }
+}
+
+/// Requests a TDX quote through configfs-tsm, binding the given report data.
+///
+/// # Arguments
+///
+/// * `name` - The name of the configfs report entry, unique to the quote's purpose
+/// * `report_data` - The data bound to the quote, zero-padded to 64 bytes
+///
+/// # Errors
+///
+/// Returns `SealingError::ReportDataError` if the report data is longer than 64 bytes, or
+/// `SealingError::IoError` if the quote cannot be generated, e.g. outside of a trust domain.
+pub fn tdx_quote(name: &str, report_data: &[u8]) -> Result<Vec<u8>> {
+    if report_data.len() > TDX_REPORT_DATA_SIZE {
+        return Err(SealingError::ReportDataError(format!(
+            "TDX report data must be at most {TDX_REPORT_DATA_SIZE} bytes long, it is {} bytes long",
+            report_data.len()
+        )));
+    }
+    let mut padded = [0u8; TDX_REPORT_DATA_SIZE];
+    padded[..report_data.len()].copy_from_slice(report_data);
+    let report_dir = Path::new(TSM_REPORT_DIR).join(format!("{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&report_dir)?;
+    let quote = std::fs::write(report_dir.join("inblob"), padded)
+        .and_then(|_| std::fs::read(report_dir.join("outblob")));
+    // The report entry is a configfs item, it must be removed even if reading failed
+    let _ = std::fs::remove_dir(&report_dir);
+    Ok(quote?)
+}
+
+/// Generates the TDX quote attesting the agent's public key, bound to the quote.
+///
+/// Outside of a trust domain no quote can be generated, and the agent refuses to run,
+/// unless `sealing.dev` is set, in which case a zeroed placeholder quote is attested.
+///
+/// # Errors
+///
+/// Returns the error of [`tdx_quote`] if the quote cannot be generated outside of
+/// development.
+pub fn attestation_quote(config: &SecretGuessingConfig, public_key: &[u8]) -> Result<Vec<u8>> {
+    match tdx_quote("attestation", public_key) {
+        Ok(quote) => Ok(quote),
+        Err(e)
+            if config
+                .sealing
+                .as_ref()
+                .and_then(|s| s.dev)
+                .unwrap_or_default() =>
+        {
+            warn!(
+                target = "sealing",
+                "Failed to generate a TDX quote, attesting a placeholder quote in development: {e}"
+            );
+            Ok(vec![0; 32])
+        }
+        Err(e) => Err(e),
+    }
+}
+
+impl KeyProvider for TdxKeyProvider {
+    fn name(&self) -> &str {
+        "tdx"
+    }
+
+    fn sealing_key(&self) -> Result<[u8; SEALING_KEY_SIZE]> {
+        if let Some(sealing_key) = self.sealing_key.get() {
+            return Ok(*sealing_key);
+        }
+        let root_key = std::fs::read(&self.root_key_path)?;
+        if root_key.len() < SEALING_KEY_SIZE {
+            return Err(SealingError::InvalidKeyError(format!(
+                "Root key must be at least {SEALING_KEY_SIZE} bytes long, it is {} bytes long",
+                root_key.len()
+            )));
+        }
+        let measurements = self.measurements()?;
+        let hkdf = Hkdf::<Sha256>::new(Some(&measurements), &root_key);
+        let mut sealing_key = [0u8; SEALING_KEY_SIZE];
+        hkdf.expand(b"secret-guessing-sealing-key", &mut sealing_key)
+            .map_err(|e| SealingError::InvalidKeyError(e.to_string()))?;
+        Ok(*self.sealing_key.get_or_init(|| sealing_key))
+    }
+}
+
+/// The game state of the current round, which must survive restarts.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SealedState {
+    /// The secret players are trying to guess
+    pub secret: String,
+
+    /// The random seed used in each inference request
+    pub random_seed: u64,
+
+    /// The client X25519 private key, registered on-chain for the round
+    pub client_private_key: [u8; 32],
+
+    /// The hints already given for the secret
+    pub hints: Vec<String>,
+
+    /// Unix timestamp (in milliseconds) at which the secret's epoch started
+    pub secret_epoch_start_ms: u64,
+
+    /// The salt of the secret's on-chain commitment
+    #[serde(default)]
+    pub secret_salt: [u8; 32],
+
+    /// Whether the secret was revealed on-chain
+    #[serde(default)]
+    pub secret_revealed: bool,
+
+    /// The number of rotations since the first round, which sets the round's theme
+    #[serde(default)]
+    pub round: u64,
+
+    /// The category of the secret
+    #[serde(default)]
+    pub category: SecretCategory,
+
+    /// The payout policy of the round, as configured when the round started
+    #[serde(default)]
+    pub payout_policy: PayoutPolicy,
+
+    /// The guess event that won the round, once its winner was paid
+    #[serde(default)]
+    pub winning_event: Option<EventID>,
+
+    /// The chain the round's commitment salt was drawn from
+    #[serde(default)]
+    pub salt_chain: Option<SaltChain>,
+}
+
+/// The on-disk, encrypted form of the sealed state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SealedEnvelope {
+    /// The version of the envelope format
+    version: u32,
+
+    /// The name of the key provider the state was sealed with
+    key_provider: String,
+
+    /// The AES-GCM nonce (base64 encoded)
+    nonce: String,
+
+    /// The encrypted state (base64 encoded)
+    ciphertext: String,
+}
+
+/// Persists the game state of the current round to disk, sealed with a key provider,
+/// so that a restart resumes the same round instead of generating a new secret.
+pub struct SealedStore {
+    /// File path of the sealed state
+    path: String,
+
+    /// The source of the sealing key
+    key_provider: Box<dyn KeyProvider>,
+
+    /// Additional authenticated data, binding the sealed state to the game's package
+    associated_data: Vec<u8>,
+}
+
+impl SealedStore {
+    /// Constructor
+    pub fn new(path: String, key_provider: Box<dyn KeyProvider>, associated_data: Vec<u8>) -> Self {
+        Self {
+            path,
+            key_provider,
+            associated_data,
+        }
+    }
+
+    /// Creates the sealed store from the application configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SealingError::ConfigError` if no key provider is configured, or if the
+    /// file key provider is configured without the `dev` flag, so that a production
+    /// deployment never seals its state with a key lying on disk by accident.
+    pub fn from_config(config: &SecretGuessingConfig) -> Result<Self> {
+        let sealing = config.sealing.clone().unwrap_or_default();
+        let key_provider: Box<dyn KeyProvider> = match sealing.key_provider {
+            Some(KeyProviderConfig::Tdx { root_key_path }) => {
+                Box::new(TdxKeyProvider::new(root_key_path))
+            }
+            Some(KeyProviderConfig::File { key_path }) if sealing.dev.unwrap_or_default() => {
+                warn!(
+                    target = "sealing",
+                    "Sealing the state with the development key file: {key_path}"
+                );
+                Box::new(FileKeyProvider::new(key_path))
+            }
+            Some(KeyProviderConfig::File { .. }) => {
+                return Err(SealingError::ConfigError(
+                    "the `file` key provider is for development only, and requires `sealing.dev = true`"
+                        .to_string(),
+                ));
+            }
+            None => {
+                return Err(SealingError::ConfigError(
+                    "`sealing.key_provider` must be configured".to_string(),
+                ));
+            }
+        };
+        Ok(Self::new(
+            sealing
+                .path
+                .unwrap_or_else(|| DEFAULT_SEALED_STATE_PATH.to_string()),
+            key_provider,
+            config.package_id.as_bytes().to_vec(),
+        ))
+    }
+
+    /// Loads and unseals the game state.
+    ///
+    /// # Returns
+    ///
+    /// The sealed state, or `None` if no state was sealed yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SealingError` if the state exists but cannot be unsealed, e.g. because it
+    /// was sealed with a different key or for a different game.
+    #[instrument(level = "info", skip_all, fields(path = %self.path))]
+    pub fn load(&self) -> Result<Option<SealedState>> {
+        let Some(envelope) = store::read_json_file::<Option<SealedEnvelope>>(&self.path)? else {
+            return Ok(None);
+        };
+        if envelope.key_provider != self.key_provider.name() {
+            return Err(SealingError::KeyProviderMismatchError(
+                envelope.key_provider,
+                self.key_provider.name().to_string(),
+            ));
+        }
+        let nonce: [u8; NONCE_SIZE] =
+            STANDARD
+                .decode(envelope.nonce)?
+                .try_into()
+                .map_err(|nonce: Vec<u8>| {
+                    SealingError::DecryptError(format!("Invalid nonce length: {}", nonce.len()))
+                })?;
+        let ciphertext = STANDARD.decode(envelope.ciphertext)?;
+        let cipher = Aes256Gcm::new(&self.key_provider.sealing_key()?.into());
+        let plaintext = cipher
+            .decrypt(
+                &nonce.into(),
+                aes_gcm::aead::Payload {
+                    msg: &ciphertext,
+                    aad: &self.associated_data,
+                },
+            )
+            .map_err(|e| SealingError::DecryptError(e.to_string()))?;
+        info!(
+            target = "sealing",
+            key_provider = self.key_provider.name(),
+            "Unsealed the game state"
+        );
+        Ok(Some(serde_json::from_slice(&plaintext)?))
+    }
+
+    /// Seals the game state to disk, replacing any previously sealed state.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SealingError` if the sealing key cannot be obtained, or the state
+    /// cannot be encrypted or written.
+    #[instrument(level = "info", skip_all, fields(path = %self.path))]
+    pub fn save(&self, state: &SealedState) -> Result<()> {
+        let plaintext = serde_json::to_vec(state)?;
+        let nonce = rand::thread_rng().gen::<[u8; NONCE_SIZE]>();
+        let cipher = Aes256Gcm::new(&self.key_provider.sealing_key()?.into());
+        let ciphertext = cipher
+            .encrypt(
+                &nonce.into(),
+                aes_gcm::aead::Payload {
+                    msg: &plaintext,
+                    aad: &self.associated_data,
+                },
+            )
+            .map_err(|e| SealingError::EncryptError(e.to_string()))?;
+        store::write_json_file(
+            &self.path,
+            &SealedEnvelope {
+                version: SEALED_ENVELOPE_VERSION,
+                key_provider: self.key_provider.name().to_string(),
+                nonce: STANDARD.encode(nonce),
+                ciphertext: STANDARD.encode(ciphertext),
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// Writes a file only readable by its owner.
+fn write_private_file(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path)?, contents)
+}
+
+#[derive(Debug, Error)]
+pub enum SealingError {
+    #[error("Invalid sealing configuration: {0}")]
+    ConfigError(String),
+    #[error("Failed to decode sealed state: {0}")]
+    DecodeError(#[from] base64::DecodeError),
+    #[error("Failed to unseal state: {0}")]
+    DecryptError(String),
+    #[error("Failed to seal state: {0}")]
+    EncryptError(String),
+    #[error("Invalid sealing key: {0}")]
+    InvalidKeyError(String),
+    #[error("Failed to read/write sealing files: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid TDX report data: {0}")]
+    ReportDataError(String),
+    #[error("State was sealed with the `{0}` key provider, but `{1}` is configured")]
+    KeyProviderMismatchError(String, String),
+    #[error("Failed to (de)serialize sealed state: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Sealed state store error: {0}")]
+    StoreError(#[from] StoreError),
+}
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::{
+    config::{Difficulty, SecretCategory, SecretGuessingConfig},
+    store::{self, StoreError},
+};
+
+/// The dictionary of nouns bundled with the agent
+const BUNDLED_WORD_LIST: &str = include_str!("../data/nouns.txt");
+
+/// The default file path of the secret history
+const DEFAULT_SECRET_HISTORY_PATH: &str = "secret_history.json";
+
+/// The default maximum number of times the model is asked for a secret, per round
+const DEFAULT_MAX_SECRET_ATTEMPTS: u32 = 5;
+
+/// Words that would make for a degenerate game, as players would try them first
+const BUILTIN_BLOCKLIST: &[&str] = &[
+    "answer",
+    "guess",
+    "item",
+    "noun",
+    "nothing",
+    "object",
+    "password",
+    "secret",
+    "something",
+    "thing",
+    "word",
+];
+
+/// The number of dictionary words suggested to the model after it picked a word
+/// outside of the dictionary
+const NUM_SUGGESTED_WORDS: usize = 20;
+
+/// How familiar a word of the dictionary is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordTier {
+    /// A word every player knows
+    Common,
+
+    /// A word most players know, but rarely use
+    Intermediate,
+
+    /// A word few players would think of
+    Rare,
+}
+
+/// The estimated difficulty of a secret.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DifficultyEstimate {
+    /// The difficulty score, from 0 (trivial) to 1 (very hard)
+    pub score: f32,
+
+    /// The difficulty tier the score falls in
+    pub difficulty: Difficulty,
+}
+
+/// A dictionary of the words accepted as secrets, along with how familiar each word is.
+#[derive(Clone, Debug, Default)]
+pub struct WordList {
+    /// Maps each word to its tier
+    words: HashMap<String, WordTier>,
+}
+
+impl WordList {
+    /// Returns the dictionary bundled with the agent.
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_WORD_LIST)
+    }
+
+    /// Parses a word list, with one word per line.
+    ///
+    /// Lines starting with `#` are comments, and `[common]`, `[intermediate]` or `[rare]`
+    /// lines set the tier of the words that follow. Words before any tier line are
+    /// deemed intermediate.
+    pub fn parse(content: &str) -> Self {
+        let mut words = HashMap::new();
+        let mut tier = WordTier::Intermediate;
+        for line in content.lines().map(str::trim) {
+            match line {
+                "" => {}
+                "[common]" => tier = WordTier::Common,
+                "[intermediate]" => tier = WordTier::Intermediate,
+                "[rare]" => tier = WordTier::Rare,
+                _ if line.starts_with('#') => {}
+                word => {
+                    words.insert(word.to_lowercase(), tier);
+                }
+            }
+        }
+        Self { words }
+    }
+
+    /// Adds the words of another word list, overriding the tier of existing words.
+    pub fn extend(&mut self, other: WordList) {
+        self.words.extend(other.words);
+    }
+
+    /// Returns the tier of a word, or `None` if the word is not in the dictionary.
+    pub fn tier(&self, word: &str) -> Option<WordTier> {
+        self.words.get(word).copied()
+    }
+
+    /// Returns all the words of the dictionary, in no particular order.
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.keys().map(String::as_str)
+    }
+
+    /// Returns all the words of the dictionary along with their tier, sorted alphabetically.
+    pub fn sorted_entries(&self) -> Vec<(String, WordTier)> {
+        let mut entries = self
+            .words
+            .iter()
+            .map(|(word, tier)| (word.clone(), *tier))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    /// The number of words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Whether the dictionary is empty.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Estimates how hard a word is to guess, from how familiar it is and its length.
+pub fn estimate_difficulty(word: &str, tier: WordTier) -> DifficultyEstimate {
+    let base: f32 = match tier {
+        WordTier::Common => 0.2,
+        WordTier::Intermediate => 0.5,
+        WordTier::Rare => 0.8,
+    };
+    // Longer words have fewer obvious associations, shorter ones are guessed by chance
+    let length_adjustment = match word.chars().count() {
+        0..=4 => -0.1,
+        5..=8 => 0.0,
+        _ => 0.1,
+    };
+    let score = (base + length_adjustment).clamp(0.0, 1.0);
+    let difficulty = if score < 0.35 {
+        Difficulty::Easy
+    } else if score < 0.65 {
+        Difficulty::Medium
+    } else {
+        Difficulty::Hard
+    };
+    DifficultyEstimate { score, difficulty }
+}
+
+/// A secret used in a past round.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SecretHistoryEntry {
+    /// The secret
+    pub secret: String,
+
+    /// The estimated difficulty of the secret
+    pub difficulty: Option<DifficultyEstimate>,
+
+    /// Unix timestamp (in seconds) at which the secret's round ended
+    pub used_at: u64,
+}
+
+/// The outcome of validating a candidate secret.
+#[derive(Clone, Debug)]
+pub enum SecretValidation {
+    /// The secret is valid, normalized to lowercase
+    Valid {
+        /// The normalized secret
+        secret: String,
+
+        /// The estimated difficulty of the secret
+        difficulty: DifficultyEstimate,
+    },
+
+    /// The secret is rejected, with the reason to give back to the model
+    Rejected(String),
+}
+
+/// Validates the secrets picked by the model, and keeps the history of past secrets.
+///
+/// A secret must be a single word, not blocklisted, and not used in a past round (within
+/// the configured repeat window, if any). English secrets outside of any theme must also be
+/// found in the dictionary, with the requested difficulty; themed or localized secrets
+/// cannot be checked against it, and are left to the model.
+pub struct SecretValidator {
+    /// The dictionary of accepted words
+    word_list: WordList,
+
+    /// Words that are never accepted
+    blocklist: HashSet<String>,
+
+    /// File path of the secret history
+    history_path: String,
+
+    /// How long, in seconds, a past secret cannot be reused. `None` means never
+    repeat_window_secs: Option<u64>,
+
+    /// Maximum number of times the model is asked for a secret, per round
+    max_attempts: u32,
+}
+
+impl SecretValidator {
+    /// Creates the secret validator from the application configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the configured word list cannot be read.
+    pub fn from_config(config: &SecretGuessingConfig) -> Result<Self, StoreError> {
+        let validation = config.secret_validation.clone().unwrap_or_default();
+        let mut word_list = WordList::bundled();
+        if let Some(path) = &validation.word_list_path {
+            word_list.extend(WordList::parse(&std::fs::read_to_string(path)?));
+        }
+        let blocklist = BUILTIN_BLOCKLIST
+            .iter()
+            .map(|word| word.to_string())
+            .chain(
+                validation
+                    .blocklist
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|word| word.to_lowercase()),
+            )
+            .collect();
+        Ok(Self {
+            word_list,
+            blocklist,
+            history_path: validation
+                .history_path
+                .unwrap_or_else(|| DEFAULT_SECRET_HISTORY_PATH.to_string()),
+            repeat_window_secs: validation
+                .repeat_window_days
+                .map(|days| days.saturating_mul(24 * 60 * 60)),
+            max_attempts: validation
+                .max_attempts
+                .unwrap_or(DEFAULT_MAX_SECRET_ATTEMPTS)
+                .max(1),
+        })
+    }
+
+    /// Maximum number of times the model is asked for a secret, per round.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the secrets used in past rounds, from the oldest to the most recent.
+    pub fn history(&self) -> Result<Vec<SecretHistoryEntry>, StoreError> {
+        store::read_json_file(&self.history_path)
+    }
+
+    /// Validates a secret picked by the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `candidate` - The secret picked by the model
+    /// * `category` - The category of the round the secret is picked for
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the secret history cannot be read.
+    #[instrument(level = "info", skip_all)]
+    pub fn validate(
+        &self,
+        candidate: &str,
+        category: &SecretCategory,
+    ) -> Result<SecretValidation, StoreError> {
+        let trimmed = candidate.trim();
+        if trimmed.is_empty() {
+            return Ok(SecretValidation::Rejected(
+                "The secret is empty".to_string(),
+            ));
+        }
+        if !trimmed.chars().all(|c| c.is_alphabetic()) {
+            return Ok(SecretValidation::Rejected(format!(
+                "\"{trimmed}\" is not a single word made only of letters"
+            )));
+        }
+        if trimmed.chars().skip(1).any(char::is_uppercase) {
+            return Ok(SecretValidation::Rejected(format!(
+                "\"{trimmed}\" looks like an acronym or a brand name"
+            )));
+        }
+        let secret = trimmed.to_lowercase();
+        let tier = self.word_list.tier(&secret);
+        // The dictionary is English and not organized by theme
+        if category.is_english() && category.theme.is_none() {
+            let Some(tier) = tier else {
+                return Ok(SecretValidation::Rejected(format!(
+                    "\"{trimmed}\" is not in the game's dictionary of common nouns, for example pick one of: {}",
+                    self.suggestions(category.difficulty)?.join(", ")
+                )));
+            };
+            let estimate = estimate_difficulty(&secret, tier);
+            if let Some(difficulty) = category
+                .difficulty
+                .filter(|difficulty| *difficulty != estimate.difficulty)
+            {
+                return Ok(SecretValidation::Rejected(format!(
+                    "\"{secret}\" is a {:?} word, but the round asks for a {difficulty:?} one, for example pick one of: {}",
+                    estimate.difficulty,
+                    self.suggestions(Some(difficulty))?.join(", ")
+                )));
+            }
+        }
+        if self.blocklist.contains(&secret) {
+            return Ok(SecretValidation::Rejected(format!(
+                "\"{secret}\" is not allowed as a secret"
+            )));
+        }
+        if let Some(entry) = self.recent_use(&secret)? {
+            return Ok(SecretValidation::Rejected(format!(
+                "\"{secret}\" was already used in a past round, at {}",
+                entry.used_at
+            )));
+        }
+        Ok(SecretValidation::Valid {
+            // Words outside of the dictionary are deemed of intermediate familiarity
+            difficulty: estimate_difficulty(&secret, tier.unwrap_or(WordTier::Intermediate)),
+            secret,
+        })
+    }
+
+    /// Records the secret of a round that just ended in the history.
+    ///
+    /// Secrets are only recorded once their round is over, as the history is stored in
+    /// the clear, unlike the sealed state of the current round.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the secret history cannot be read or written.
+    pub fn record(&self, secret: &str) -> Result<(), StoreError> {
+        let secret = secret.to_lowercase();
+        let difficulty = self
+            .word_list
+            .tier(&secret)
+            .map(|tier| estimate_difficulty(&secret, tier));
+        let mut history = self.history()?;
+        history.push(SecretHistoryEntry {
+            secret,
+            difficulty,
+            used_at: store::unix_timestamp_secs(),
+        });
+        store::write_json_file(&self.history_path, &history)?;
+        info!(
+            target = "secret-validation",
+            num_secrets = history.len(),
+            "Recorded the secret of the ended round in the history"
+        );
+        Ok(())
+    }
+
+    /// Returns the most recent use of a secret within the repeat window, if any.
+    fn recent_use(&self, secret: &str) -> Result<Option<SecretHistoryEntry>, StoreError> {
+        let now = store::unix_timestamp_secs();
+        Ok(self.history()?.into_iter().rev().find(|entry| {
+            entry.secret == secret
+                && match self.repeat_window_secs {
+                    Some(window) => now.saturating_sub(entry.used_at) < window,
+                    None => true,
+                }
+        }))
+    }
+
+    /// Picks a few random dictionary words of the given difficulty, if any, that are
+    /// neither blocklisted nor recently used.
+    fn suggestions(&self, difficulty: Option<Difficulty>) -> Result<Vec<String>, StoreError> {
+        let used = self
+            .history()?
+            .into_iter()
+            .map(|entry| entry.secret)
+            .collect::<HashSet<_>>();
+        Ok(self
+            .word_list
+            .words()
+            .filter(|word| !self.blocklist.contains(*word) && !used.contains(*word))
+            .filter(|word| match difficulty {
+                Some(difficulty) => self
+                    .word_list
+                    .tier(word)
+                    .is_some_and(|tier| estimate_difficulty(word, tier).difficulty == difficulty),
+                None => true,
+            })
+            .map(str::to_string)
+            .choose_multiple(&mut rand::thread_rng(), NUM_SUGGESTED_WORDS))
+    }
+}
@@ -0,0 +1,302 @@
+use blake2::{
+    digest::{consts::U32, KeyInit, Mac},
+    Blake2bMac,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    atoma::utils::blake2b_hash,
+    commitment::COMMITMENT_SALT_SIZE,
+    config::{SecretGuessingConfig, SecretSourceConfig},
+    secret_validation::{estimate_difficulty, DifficultyEstimate, WordList, WordTier},
+};
+
+type Result<T> = std::result::Result<T, SelectionError>;
+
+/// Domain separation tag of the keyed hash indexing the word list
+const SELECTION_DOMAIN: &[u8] = b"secret-guessing/secret-selection/v1";
+
+/// The number of rounds whose salts are committed to by a single salt chain
+pub const SALT_CHAIN_LENGTH: u64 = 4096;
+
+/// A secret picked from the word list.
+#[derive(Clone, Debug)]
+pub struct SelectedSecret {
+    /// The secret
+    pub secret: String,
+
+    /// The index of the secret in the sorted word list
+    pub index: usize,
+
+    /// The estimated difficulty of the secret
+    pub difficulty: DifficultyEstimate,
+}
+
+/// Derives each round's secret from the on-chain random seed and a committed word list,
+/// instead of letting the model pick it.
+///
+/// The secret is the word at index `keyed_hash(salt, random_seed, digest) mod num_words`
+/// of the alphabetically sorted word list, where `digest` commits to the word list and
+/// `salt` is the salt of the round's secret commitment. The salt is kept private until the
+/// secret is revealed, so players cannot derive the secret from the public random seed.
+/// Once the secret and salt are revealed, anyone holding the word list can check that the
+/// secret was selected as committed.
+///
+/// The salts come from a `SaltChain`, so each revealed salt commits to the salt of the
+/// next round before the next random seed is emitted, and the agent cannot grind salts
+/// for a secret of its liking. The first round of each chain (in particular the very first
+/// round, whose random seed is drawn locally rather than emitted on-chain) is not linked
+/// to any earlier reveal, so its selection cannot be verified to be unbiased.
+pub struct SecretSelector {
+    /// The words to select from, along with their tier, sorted alphabetically
+    entries: Vec<(String, WordTier)>,
+
+    /// The Blake2b-256 hash of the sorted words, separated by line breaks
+    word_list_digest: [u8; 32],
+}
+
+impl SecretSelector {
+    /// Constructor
+    ///
+    /// # Errors
+    ///
+    /// Returns `SelectionError::EmptyWordList` if the word list has no words.
+    pub fn new(word_list: &WordList) -> Result<Self> {
+        let entries = word_list.sorted_entries();
+        if entries.is_empty() {
+            return Err(SelectionError::EmptyWordList);
+        }
+        let canonical = entries
+            .iter()
+            .map(|(word, _)| word.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Self {
+            word_list_digest: blake2b_hash(canonical.as_bytes()).into(),
+            entries,
+        })
+    }
+
+    /// Creates the secret selector from the application configuration.
+    ///
+    /// The bundled dictionary is used, unless the configured secret source points to
+    /// another word list, which then replaces it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SelectionError` if the configured word list cannot be read or is empty.
+    pub fn from_config(config: &SecretGuessingConfig) -> Result<Self> {
+        let word_list = match &config.secret_source {
+            Some(SecretSourceConfig::WordList {
+                word_list_path: Some(path),
+            }) => WordList::parse(&std::fs::read_to_string(path)?),
+            _ => WordList::bundled(),
+        };
+        Self::new(&word_list)
+    }
+
+    /// The commitment to the word list, to be published along with the agent's release.
+    pub fn word_list_digest(&self) -> [u8; 32] {
+        self.word_list_digest
+    }
+
+    /// The number of words to select from.
+    pub fn num_words(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Selects the secret of a round.
+    ///
+    /// # Arguments
+    ///
+    /// * `random_seed` - The round's random seed, as emitted on-chain
+    /// * `salt` - The salt of the round's secret commitment, used as the hash key
+    pub fn select(&self, random_seed: u64, salt: &[u8]) -> SelectedSecret {
+        let index = selection_index(salt, random_seed, &self.word_list_digest, self.num_words());
+        let (secret, tier) = &self.entries[index];
+        SelectedSecret {
+            secret: secret.clone(),
+            index,
+            difficulty: estimate_difficulty(secret, *tier),
+        }
+    }
+
+    /// Checks that a revealed secret was selected from the word list as committed.
+    ///
+    /// # Arguments
+    ///
+    /// * `random_seed` - The round's random seed, as emitted on-chain
+    /// * `salt` - The revealed salt of the round's secret commitment
+    /// * `secret` - The revealed secret
+    pub fn verify(&self, random_seed: u64, salt: &[u8], secret: &str) -> bool {
+        self.select(random_seed, salt).secret == secret
+    }
+}
+
+/// Computes the index of a round's secret in a word list.
+///
+/// The index is derived from a Blake2b-256 MAC, keyed with the salt, over the random
+/// seed (as little-endian bytes) and the word list digest. The first 16 bytes of the MAC
+/// are reduced modulo the number of words, which makes the modulo bias negligible.
+///
+/// # Panics
+///
+/// Panics if `salt` is longer than 64 bytes, or if `num_words` is zero.
+pub fn selection_index(
+    salt: &[u8],
+    random_seed: u64,
+    word_list_digest: &[u8; 32],
+    num_words: usize,
+) -> usize {
+    let mut mac =
+        <Blake2bMac<U32> as KeyInit>::new_from_slice(salt).expect("salt is at most 64 bytes");
+    mac.update(SELECTION_DOMAIN);
+    mac.update(&random_seed.to_le_bytes());
+    mac.update(word_list_digest);
+    let output = mac.finalize().into_bytes();
+    let mut value = [0; 16];
+    value.copy_from_slice(&output[..16]);
+    (u128::from_le_bytes(value) % num_words as u128) as usize
+}
+
+/// A hash chain of commitment salts, in which each round's salt is the Blake2b-256 hash
+/// of the next round's salt.
+///
+/// The chain is drawn from a random seed when it starts, by hashing the seed once per
+/// round, and its salts are used in reverse order. Revealing a round's salt thus commits
+/// to the salt of the next round, which anyone can check with `verify_chained_salt` once
+/// it is revealed in turn.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaltChain {
+    /// The random seed of the chain, i.e. the salt of its last round
+    seed: [u8; COMMITMENT_SALT_SIZE],
+
+    /// The position of the current round in the chain, from `0` to `SALT_CHAIN_LENGTH - 1`
+    position: u64,
+}
+
+impl SaltChain {
+    /// Starts a new chain, from a fresh random seed.
+    pub fn generate() -> Self {
+        Self {
+            seed: rand::thread_rng().gen(),
+            position: 0,
+        }
+    }
+
+    /// The position of the current round in the chain.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The salt of the current round.
+    pub fn salt(&self) -> [u8; COMMITMENT_SALT_SIZE] {
+        (self.position + 1..SALT_CHAIN_LENGTH).fold(self.seed, |salt, _| blake2b_hash(&salt).into())
+    }
+
+    /// The chain moved to the next round, or `None` if the chain is exhausted.
+    pub fn next(&self) -> Option<Self> {
+        (self.position + 1 < SALT_CHAIN_LENGTH).then(|| Self {
+            seed: self.seed,
+            position: self.position + 1,
+        })
+    }
+}
+
+/// Checks that a revealed salt is the one the previous round's revealed salt committed to.
+pub fn verify_chained_salt(previous_salt: &[u8], salt: &[u8]) -> bool {
+    blake2b_hash(salt).as_slice() == previous_salt
+}
+
+#[derive(Debug, Error)]
+pub enum SelectionError {
+    #[error("Failed to read the word list: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("The word list has no words")]
+    EmptyWordList,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: [u8; 32] = [7; 32];
+
+    #[test]
+    fn selection_index_is_deterministic_and_in_range() {
+        for num_words in [1, 2, 3, 1000] {
+            let index = selection_index(&[1; 32], 42, &DIGEST, num_words);
+            assert!(index < num_words);
+            assert_eq!(index, selection_index(&[1; 32], 42, &DIGEST, num_words));
+        }
+        assert_eq!(selection_index(&[1; 32], 42, &DIGEST, 1), 0);
+    }
+
+    #[test]
+    fn selection_index_depends_on_every_input() {
+        let num_words = 1 << 20;
+        let index = selection_index(&[1; 32], 42, &DIGEST, num_words);
+        assert_ne!(index, selection_index(&[2; 32], 42, &DIGEST, num_words));
+        assert_ne!(index, selection_index(&[1; 32], 43, &DIGEST, num_words));
+        assert_ne!(index, selection_index(&[1; 32], 42, &[8; 32], num_words));
+    }
+
+    #[test]
+    fn selection_index_spreads_over_the_word_list() {
+        let num_words = 8;
+        let mut counts = [0; 8];
+        for random_seed in 0..8000 {
+            counts[selection_index(&[1; 32], random_seed, &DIGEST, num_words)] += 1;
+        }
+        assert!(
+            counts.iter().all(|count| (800..1200).contains(count)),
+            "{counts:?}"
+        );
+    }
+
+    #[test]
+    fn selector_verifies_only_the_selected_secret() {
+        let selector =
+            SecretSelector::new(&WordList::parse("lamp\nriver\nbridge\ncastle")).unwrap();
+        assert_eq!(selector.num_words(), 4);
+        let selected = selector.select(42, &[1; 32]);
+        assert!(selector.verify(42, &[1; 32], &selected.secret));
+        let other = ["bridge", "castle", "lamp", "river"]
+            .into_iter()
+            .find(|word| *word != selected.secret)
+            .unwrap();
+        assert!(!selector.verify(42, &[1; 32], other));
+    }
+
+    #[test]
+    fn empty_word_list_is_rejected() {
+        assert!(matches!(
+            SecretSelector::new(&WordList::parse("# no words")),
+            Err(SelectionError::EmptyWordList)
+        ));
+    }
+
+    #[test]
+    fn each_salt_of_a_chain_commits_to_the_next_one() {
+        let mut chain = SaltChain::generate();
+        for _ in 0..3 {
+            let next = chain.next().unwrap();
+            assert!(verify_chained_salt(&chain.salt(), &next.salt()));
+            assert!(!verify_chained_salt(&next.salt(), &chain.salt()));
+            chain = next;
+        }
+        assert_eq!(chain.position(), 3);
+    }
+
+    #[test]
+    fn salt_chain_ends_after_its_length() {
+        let chain = SaltChain {
+            seed: [3; 32],
+            position: SALT_CHAIN_LENGTH - 1,
+        };
+        assert_eq!(chain.salt(), [3; 32]);
+        assert!(chain.next().is_none());
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    marker::PhantomData,
+    path::Path,
+};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, StoreError>;
+
+/// Reads a JSON document from the given file path.
+///
+/// If the file doesn't exist, the default value for `T` is returned, so that
+/// stores backed by this function start out empty on a fresh deployment.
+///
+/// # Arguments
+///
+/// * `path` - The path to the JSON file
+///
+/// # Errors
+///
+/// Returns a `StoreError` if the file exists but cannot be read or parsed.
+pub(crate) fn read_json_file<T: DeserializeOwned + Default>(path: &str) -> Result<T> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(StoreError::IoError(e)),
+    };
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Writes a JSON document to the given file path.
+///
+/// The document is first written to a temporary file next to `path`, which is then
+/// renamed over the destination, so that a crash mid-write never leaves a truncated file.
+///
+/// # Arguments
+///
+/// * `path` - The path to the JSON file
+/// * `value` - The value to serialize
+///
+/// # Errors
+///
+/// Returns a `StoreError` if serialization or any of the file operations fail.
+pub(crate) fn write_json_file<T: Serialize>(path: &str, value: &T) -> Result<()> {
+    create_parent_dir(path)?;
+    let tmp_path = format!("{path}.tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(value)?)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// An exclusive lock on a store, released when dropped.
+pub(crate) struct StoreLock {
+    _file: File,
+}
+
+/// Takes an exclusive lock on the store at the given file path, blocking until it is free.
+///
+/// Stores read, modify and write back their whole file, and the running engine and the
+/// CLI operate on the same files, so every read-modify-write cycle must hold the lock,
+/// lest one process overwrites the changes of the other. The lock is taken on a
+/// `<path>.lock` file next to the store, as the store file itself is replaced on each write.
+///
+/// # Arguments
+///
+/// * `path` - The path to the JSON file of the store
+///
+/// # Errors
+///
+/// Returns a `StoreError` if the lock file cannot be created or locked.
+pub(crate) fn lock_file(path: &str) -> Result<StoreLock> {
+    create_parent_dir(path)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(format!("{path}.lock"))?;
+    file.lock()?;
+    Ok(StoreLock { _file: file })
+}
+
+/// A persistent map of entries keyed by their identifier, stored as a JSON object.
+///
+/// Every operation reads the backing file, and every update holds the store's lock for
+/// its whole read-modify-write cycle, so that the running engine and the CLI can operate
+/// on the same store without sharing any in-memory state.
+pub(crate) struct JsonStore<T> {
+    /// File path of the store
+    path: String,
+
+    _entries: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for JsonStore<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.path.clone())
+    }
+}
+
+impl<T> JsonStore<T> {
+    /// Constructor
+    pub(crate) fn new(path: String) -> Self {
+        Self {
+            path,
+            _entries: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> JsonStore<T> {
+    /// Returns all the entries, keyed by their identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the file exists but cannot be read or parsed.
+    pub(crate) fn read(&self) -> Result<BTreeMap<String, T>> {
+        read_json_file(&self.path)
+    }
+
+    /// Returns all the entries, ordered by their identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the file exists but cannot be read or parsed.
+    pub(crate) fn values(&self) -> Result<Vec<T>> {
+        Ok(self.read()?.into_values().collect())
+    }
+
+    /// Updates the entries under the store's lock. They are written back only if the
+    /// update succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the update, or a `StoreError` if the store cannot be locked,
+    /// read or written.
+    pub(crate) fn update<R, E: From<StoreError>>(
+        &self,
+        update: impl FnOnce(&mut BTreeMap<String, T>) -> std::result::Result<R, E>,
+    ) -> std::result::Result<R, E> {
+        let _lock = lock_file(&self.path)?;
+        let mut entries = self.read()?;
+        let result = update(&mut entries)?;
+        write_json_file(&self.path, &entries)?;
+        Ok(result)
+    }
+
+    /// Removes an entry, if it exists.
+    ///
+    /// # Returns
+    ///
+    /// The removed entry, or `None` if there is no entry with this identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the store cannot be locked, read or written.
+    pub(crate) fn remove(&self, id: &str) -> Result<Option<T>> {
+        let _lock = lock_file(&self.path)?;
+        let mut entries = self.read()?;
+        let entry = entries.remove(id);
+        if entry.is_some() {
+            write_json_file(&self.path, &entries)?;
+        }
+        Ok(entry)
+    }
+}
+
+/// Creates the parent directory of a file path, if it doesn't exist.
+fn create_parent_dir(path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the current Unix timestamp, in seconds.
+pub(crate) fn unix_timestamp_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("Failed to read/write store file: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to (de)serialize store file: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> JsonStore<u64> {
+        let path = std::env::temp_dir().join(format!(
+            "secret-guessing-{name}-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        JsonStore::new(path.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn failed_update_is_not_written() {
+        let store = temp_store("failed-update");
+        store
+            .update(|entries| {
+                entries.insert("a".to_string(), 1);
+                Ok::<_, StoreError>(())
+            })
+            .unwrap();
+        let result = store.update(|entries| {
+            entries.insert("b".to_string(), 2);
+            Err::<(), _>(StoreError::IoError(std::io::ErrorKind::Other.into()))
+        });
+        assert!(result.is_err());
+        assert_eq!(store.values().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn removing_a_missing_entry_leaves_the_store_unwritten() {
+        let store = temp_store("remove-missing");
+        assert!(store.remove("a").unwrap().is_none());
+        assert!(!Path::new(&store.path).exists());
+        store
+            .update(|entries| {
+                entries.insert("a".to_string(), 1);
+                Ok::<_, StoreError>(())
+            })
+            .unwrap();
+        assert_eq!(store.remove("a").unwrap(), Some(1));
+        assert!(store.values().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,531 @@
+use sui_sdk::types::{
+    base_types::{ObjectID, ObjectRef, SuiAddress},
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{Argument, Command, ObjectArg, ProgrammableTransaction},
+    Identifier,
+};
+
+use crate::SECRET_GUESSING_MODULE_NAME;
+
+/// The name of the function to withdraw funds from the treasury pool
+pub(crate) const WITHDRAW_FUNDS_FROM_TREASURY_POOL_FUNCTION_NAME: &str =
+    "withdraw_funds_from_treasury_pool";
+
+/// The name of the function to pay given amounts out of the treasury pool
+pub(crate) const WITHDRAW_PAYOUTS_FUNCTION_NAME: &str = "withdraw_payouts";
+
+/// The name of the function to submit the node public key
+pub(crate) const RESUBMIT_TDX_ATTESTATION_FUNCTION_NAME: &str = "resubmit_tdx_attestation";
+
+/// The name of the function to commit to the secret of a new round
+pub(crate) const COMMIT_SECRET_FUNCTION_NAME: &str = "commit_secret";
+
+/// The name of the function to reveal the secret of a round
+pub(crate) const REVEAL_SECRET_FUNCTION_NAME: &str = "reveal_secret";
+
+/// The name of the function to submit a guess
+pub(crate) const SUBMIT_GUESS_FUNCTION_NAME: &str = "submit_guess";
+
+/// The name of the function to pause or unpause the game
+pub(crate) const SET_PAUSED_FUNCTION_NAME: &str = "set_paused";
+
+/// The name of the function to change the fee of a guess
+pub(crate) const SET_FEE_FUNCTION_NAME: &str = "set_fee";
+
+/// The name of the function to add funds to the treasury pool
+pub(crate) const SEED_TREASURY_FUNCTION_NAME: &str = "seed_treasury";
+
+/// The name of the function to hand the admin capability over to a new admin
+pub(crate) const ROTATE_ADMIN_FUNCTION_NAME: &str = "rotate_admin";
+
+/// The name of the struct of the admin capability
+pub(crate) const ADMIN_CAP_STRUCT_NAME: &str = "AdminCap";
+
+/// The name reported for transactions batching several calls
+pub(crate) const BATCH_FUNCTION_NAME: &str = "batch";
+
+/// A call to an entry function of the Secret Guessing module, with its BCS-typed
+/// arguments. The shared Secret Guessing database object is always the first argument,
+/// and is added when the call is appended to a programmable transaction.
+///
+/// The argument layout of each call is pinned by the module's interface in
+/// `data/secret_guessing_abi.json`, in the format of the `sui_getNormalizedMoveModule`
+/// RPC method, so that it can be compared with the deployed package.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecretGuessingCall {
+    /// `resubmit_tdx_attestation(db, public_key: vector<u8>, tdx_quote: vector<u8>)`
+    ResubmitTdxAttestation {
+        /// The node's X25519 public key
+        public_key: Vec<u8>,
+
+        /// The TDX quote attesting the public key
+        tdx_quote: Vec<u8>,
+    },
+
+    /// `withdraw_funds_from_treasury_pool(db, winner: address)`
+    WithdrawFundsFromTreasuryPool {
+        /// The address receiving the treasury pool
+        winner: SuiAddress,
+    },
+
+    /// `withdraw_payouts(db, recipients: vector<address>, amounts: vector<u64>)`
+    WithdrawPayouts {
+        /// The addresses paid out of the treasury pool
+        recipients: Vec<SuiAddress>,
+
+        /// The amount paid to each recipient, in MIST
+        amounts: Vec<u64>,
+    },
+
+    /// `commit_secret(db, commitment: vector<u8>)`
+    CommitSecret {
+        /// The salted hash of the secret
+        commitment: Vec<u8>,
+    },
+
+    /// `reveal_secret(db, secret: vector<u8>, salt: vector<u8>)`
+    RevealSecret {
+        /// The UTF-8 bytes of the secret
+        secret: Vec<u8>,
+
+        /// The salt of the secret's commitment
+        salt: Vec<u8>,
+    },
+
+    /// `submit_guess(db, guess: String, fee: Coin<SUI>)`
+    SubmitGuess {
+        /// The guess
+        guess: String,
+
+        /// The fee paid for the guess, split from the gas coin
+        fee: u64,
+    },
+
+    /// `set_paused(db, cap: &AdminCap, paused: bool)`
+    SetPaused {
+        /// The admin capability
+        cap: ObjectRef,
+
+        /// Whether guesses are refused
+        paused: bool,
+    },
+
+    /// `set_fee(db, cap: &AdminCap, fee: u64)`
+    SetFee {
+        /// The admin capability
+        cap: ObjectRef,
+
+        /// The new fee of a guess, in MIST
+        fee: u64,
+    },
+
+    /// `seed_treasury(db, cap: &AdminCap, funds: Coin<SUI>)`
+    SeedTreasury {
+        /// The admin capability
+        cap: ObjectRef,
+
+        /// The amount added to the treasury pool, split from the gas coin
+        amount: u64,
+    },
+
+    /// `rotate_admin(db, cap: AdminCap, new_admin: address)`
+    RotateAdmin {
+        /// The admin capability, transferred to the new admin
+        cap: ObjectRef,
+
+        /// The address of the new admin
+        new_admin: SuiAddress,
+    },
+}
+
+impl SecretGuessingCall {
+    /// The name of the Move function called.
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            Self::ResubmitTdxAttestation { .. } => RESUBMIT_TDX_ATTESTATION_FUNCTION_NAME,
+            Self::WithdrawFundsFromTreasuryPool { .. } => {
+                WITHDRAW_FUNDS_FROM_TREASURY_POOL_FUNCTION_NAME
+            }
+            Self::WithdrawPayouts { .. } => WITHDRAW_PAYOUTS_FUNCTION_NAME,
+            Self::CommitSecret { .. } => COMMIT_SECRET_FUNCTION_NAME,
+            Self::RevealSecret { .. } => REVEAL_SECRET_FUNCTION_NAME,
+            Self::SubmitGuess { .. } => SUBMIT_GUESS_FUNCTION_NAME,
+            Self::SetPaused { .. } => SET_PAUSED_FUNCTION_NAME,
+            Self::SetFee { .. } => SET_FEE_FUNCTION_NAME,
+            Self::SeedTreasury { .. } => SEED_TREASURY_FUNCTION_NAME,
+            Self::RotateAdmin { .. } => ROTATE_ADMIN_FUNCTION_NAME,
+        }
+    }
+
+    /// The amount of SUI (in MIST) the call pays out of the gas coin, on top of the gas.
+    pub fn payment(&self) -> u64 {
+        match self {
+            Self::SubmitGuess { fee, .. } => *fee,
+            Self::SeedTreasury { amount, .. } => *amount,
+            _ => 0,
+        }
+    }
+
+    /// Appends the call to a programmable transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The programmable transaction builder
+    /// * `package` - The ID of the Secret Guessing package
+    /// * `db` - The shared Secret Guessing database object
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an argument cannot be serialized.
+    pub fn append(
+        &self,
+        builder: &mut ProgrammableTransactionBuilder,
+        package: ObjectID,
+        db: ObjectArg,
+    ) -> anyhow::Result<()> {
+        let mut arguments = vec![builder.obj(db)?];
+        match self {
+            Self::ResubmitTdxAttestation {
+                public_key,
+                tdx_quote,
+            } => {
+                arguments.push(builder.pure(public_key)?);
+                arguments.push(builder.pure(tdx_quote)?);
+            }
+            Self::WithdrawFundsFromTreasuryPool { winner } => {
+                arguments.push(builder.pure(winner)?);
+            }
+            Self::WithdrawPayouts {
+                recipients,
+                amounts,
+            } => {
+                arguments.push(builder.pure(recipients)?);
+                arguments.push(builder.pure(amounts)?);
+            }
+            Self::CommitSecret { commitment } => {
+                arguments.push(builder.pure(commitment)?);
+            }
+            Self::RevealSecret { secret, salt } => {
+                arguments.push(builder.pure(secret)?);
+                arguments.push(builder.pure(salt)?);
+            }
+            Self::SubmitGuess { guess, fee } => {
+                arguments.push(builder.pure(guess)?);
+                arguments.push(split_gas_coin(builder, *fee)?);
+            }
+            Self::SetPaused { cap, paused } => {
+                arguments.push(builder.obj(ObjectArg::ImmOrOwnedObject(*cap))?);
+                arguments.push(builder.pure(paused)?);
+            }
+            Self::SetFee { cap, fee } => {
+                arguments.push(builder.obj(ObjectArg::ImmOrOwnedObject(*cap))?);
+                arguments.push(builder.pure(fee)?);
+            }
+            Self::SeedTreasury { cap, amount } => {
+                arguments.push(builder.obj(ObjectArg::ImmOrOwnedObject(*cap))?);
+                arguments.push(split_gas_coin(builder, *amount)?);
+            }
+            Self::RotateAdmin { cap, new_admin } => {
+                arguments.push(builder.obj(ObjectArg::ImmOrOwnedObject(*cap))?);
+                arguments.push(builder.pure(new_admin)?);
+            }
+        }
+        builder.programmable_move_call(
+            package,
+            Identifier::new(SECRET_GUESSING_MODULE_NAME)?,
+            Identifier::new(self.function_name())?,
+            vec![],
+            arguments,
+        );
+        Ok(())
+    }
+}
+
+/// Splits a coin of the given amount from the gas coin, and returns it as an argument.
+fn split_gas_coin(
+    builder: &mut ProgrammableTransactionBuilder,
+    amount: u64,
+) -> anyhow::Result<Argument> {
+    let amount = builder.pure(amount)?;
+    let Argument::Result(coins) =
+        builder.command(Command::SplitCoins(Argument::GasCoin, vec![amount]))
+    else {
+        unreachable!("commands always return a result argument");
+    };
+    Ok(Argument::NestedResult(coins, 0))
+}
+
+/// Builds a programmable transaction running the given calls in order, atomically.
+///
+/// # Errors
+///
+/// Returns an error if an argument cannot be serialized.
+pub fn build_programmable_transaction(
+    calls: &[SecretGuessingCall],
+    package: ObjectID,
+    db: ObjectArg,
+) -> anyhow::Result<ProgrammableTransaction> {
+    let mut builder = ProgrammableTransactionBuilder::new();
+    for call in calls {
+        call.append(&mut builder, package, db)?;
+    }
+    Ok(builder.finish())
+}
+
+/// The amount of SUI (in MIST) the given calls pay out of the gas coin, on top of the gas.
+pub(crate) fn payment(calls: &[SecretGuessingCall]) -> u64 {
+    calls.iter().map(SecretGuessingCall::payment).sum()
+}
+
+/// The name reported for a transaction running the given calls.
+pub(crate) fn function_name(calls: &[SecretGuessingCall]) -> &'static str {
+    match calls {
+        [call] => call.function_name(),
+        _ => BATCH_FUNCTION_NAME,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use sui_sdk::types::{
+        base_types::{ObjectDigest, SequenceNumber},
+        transaction::CallArg,
+    };
+
+    use super::*;
+
+    /// The normalized `secret_guessing` module the calls are built against
+    const ABI: &str = include_str!("../data/secret_guessing_abi.json");
+
+    fn admin_cap() -> ObjectRef {
+        (
+            ObjectID::random(),
+            SequenceNumber::from(1),
+            ObjectDigest::random(),
+        )
+    }
+
+    fn all_calls() -> Vec<SecretGuessingCall> {
+        vec![
+            SecretGuessingCall::ResubmitTdxAttestation {
+                public_key: vec![1; 32],
+                tdx_quote: vec![2; 64],
+            },
+            SecretGuessingCall::WithdrawFundsFromTreasuryPool {
+                winner: SuiAddress::random_for_testing_only(),
+            },
+            SecretGuessingCall::WithdrawPayouts {
+                recipients: vec![
+                    SuiAddress::random_for_testing_only(),
+                    SuiAddress::random_for_testing_only(),
+                ],
+                amounts: vec![60, 40],
+            },
+            SecretGuessingCall::CommitSecret {
+                commitment: vec![3; 32],
+            },
+            SecretGuessingCall::RevealSecret {
+                secret: b"cat".to_vec(),
+                salt: vec![4; 32],
+            },
+            SecretGuessingCall::SubmitGuess {
+                guess: "dog".to_string(),
+                fee: 100,
+            },
+            SecretGuessingCall::SetPaused {
+                cap: admin_cap(),
+                paused: true,
+            },
+            SecretGuessingCall::SetFee {
+                cap: admin_cap(),
+                fee: 200,
+            },
+            SecretGuessingCall::SeedTreasury {
+                cap: admin_cap(),
+                amount: 1_000,
+            },
+            SecretGuessingCall::RotateAdmin {
+                cap: admin_cap(),
+                new_admin: SuiAddress::random_for_testing_only(),
+            },
+        ]
+    }
+
+    /// Returns the name of a normalized struct type, if it is one.
+    fn struct_name(ty: &Value) -> Option<&str> {
+        ty.get("Struct")?.get("name")?.as_str()
+    }
+
+    /// Returns the size of an element of a normalized vector type, if it has a fixed size.
+    fn element_size(ty: &Value) -> Option<usize> {
+        match ty.as_str()? {
+            "Bool" | "U8" => Some(1),
+            "U64" => Some(8),
+            "Address" => Some(32),
+            _ => None,
+        }
+    }
+
+    /// Reads the ULEB128 length prefix of a BCS vector, and returns it with the rest.
+    fn split_length(bytes: &[u8]) -> (usize, &[u8]) {
+        let mut length = 0;
+        for (i, byte) in bytes.iter().enumerate() {
+            length |= usize::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return (length, &bytes[i + 1..]);
+            }
+        }
+        panic!("unterminated ULEB128 length");
+    }
+
+    /// Checks that an argument of a call matches the type of its parameter.
+    fn check_argument(
+        function: &str,
+        parameter: &Value,
+        argument: &Argument,
+        inputs: &[CallArg],
+        commands: &[Command],
+    ) {
+        let by_reference = parameter
+            .get("Reference")
+            .or_else(|| parameter.get("MutableReference"));
+        match (by_reference.and_then(struct_name), struct_name(parameter)) {
+            (Some(_), _) | (None, Some(ADMIN_CAP_STRUCT_NAME)) => {
+                let Argument::Input(index) = argument else {
+                    panic!("{function}: {parameter} must be an object input, got {argument:?}");
+                };
+                assert!(
+                    matches!(inputs[usize::from(*index)], CallArg::Object(_)),
+                    "{function}: {parameter} must be an object input"
+                );
+            }
+            (None, Some("Coin")) => {
+                let Argument::NestedResult(command, 0) = argument else {
+                    panic!("{function}: {parameter} must be a split coin, got {argument:?}");
+                };
+                assert!(
+                    matches!(
+                        commands[usize::from(*command)],
+                        Command::SplitCoins(Argument::GasCoin, _)
+                    ),
+                    "{function}: {parameter} must be split from the gas coin"
+                );
+            }
+            (None, name) => {
+                let Argument::Input(index) = argument else {
+                    panic!("{function}: {parameter} must be a pure input, got {argument:?}");
+                };
+                let CallArg::Pure(bytes) = &inputs[usize::from(*index)] else {
+                    panic!("{function}: {parameter} must be a pure input");
+                };
+                let size = match (name, parameter.get("Vector")) {
+                    (Some("String"), _) => {
+                        let (length, rest) = split_length(bytes);
+                        length + bytes.len() - rest.len()
+                    }
+                    (_, Some(element)) => {
+                        let (length, rest) = split_length(bytes);
+                        let element_size = element_size(element)
+                            .unwrap_or_else(|| panic!("{function}: unexpected {parameter}"));
+                        length * element_size + bytes.len() - rest.len()
+                    }
+                    _ => element_size(parameter)
+                        .unwrap_or_else(|| panic!("{function}: unexpected {parameter}")),
+                };
+                assert_eq!(
+                    bytes.len(),
+                    size,
+                    "{function}: the BCS layout of {parameter} does not match"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn calls_match_the_module_interface() {
+        let abi: Value = serde_json::from_str(ABI).unwrap();
+        assert_eq!(abi["name"], SECRET_GUESSING_MODULE_NAME);
+        assert!(abi["structs"].get(ADMIN_CAP_STRUCT_NAME).is_some());
+        let db = ObjectArg::SharedObject {
+            id: ObjectID::random(),
+            initial_shared_version: SequenceNumber::from(1),
+            mutable: true,
+        };
+
+        let calls = all_calls();
+        for call in &calls {
+            let function = call.function_name();
+            let abi_function = &abi["exposedFunctions"][function];
+            assert_eq!(
+                abi_function["isEntry"], true,
+                "{function} is not an entry function of the module"
+            );
+            // The transaction context is passed by the runtime, not by the caller
+            let parameters: Vec<&Value> = abi_function["parameters"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|parameter| {
+                    let context = parameter.get("MutableReference").and_then(struct_name);
+                    context != Some("TxContext")
+                })
+                .collect();
+
+            let transaction =
+                build_programmable_transaction(std::slice::from_ref(call), ObjectID::random(), db)
+                    .unwrap();
+            let Some(Command::MoveCall(move_call)) = transaction.commands.last() else {
+                panic!("{function}: the last command must be the call");
+            };
+            assert_eq!(move_call.module.as_str(), SECRET_GUESSING_MODULE_NAME);
+            assert_eq!(move_call.function.as_str(), function);
+            assert_eq!(
+                move_call.arguments.len(),
+                parameters.len(),
+                "{function}: the number of arguments does not match"
+            );
+            for (parameter, argument) in parameters.iter().zip(&move_call.arguments) {
+                check_argument(
+                    function,
+                    parameter,
+                    argument,
+                    &transaction.inputs,
+                    &transaction.commands,
+                );
+            }
+        }
+
+        // Every entry function the agent relies on is exercised above
+        let exposed = abi["exposedFunctions"].as_object().unwrap();
+        assert_eq!(exposed.len(), calls.len());
+    }
+
+    #[test]
+    fn batched_calls_share_the_database_object() {
+        let db = ObjectArg::SharedObject {
+            id: ObjectID::random(),
+            initial_shared_version: SequenceNumber::from(1),
+            mutable: true,
+        };
+        let calls = [
+            SecretGuessingCall::ResubmitTdxAttestation {
+                public_key: vec![1; 32],
+                tdx_quote: vec![2; 64],
+            },
+            SecretGuessingCall::CommitSecret {
+                commitment: vec![3; 32],
+            },
+        ];
+        let transaction = build_programmable_transaction(&calls, ObjectID::random(), db).unwrap();
+        assert_eq!(transaction.commands.len(), 2);
+        let objects = transaction
+            .inputs
+            .iter()
+            .filter(|input| matches!(input, CallArg::Object(_)))
+            .count();
+        assert_eq!(objects, 1);
+        assert_eq!(function_name(&calls), BATCH_FUNCTION_NAME);
+        assert_eq!(payment(&calls), 0);
+    }
+}
@@ -212,6 +212,59 @@ pub struct ChatCompletionChunkDelta {
     pub tool_calls: Option<Vec<Value>>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateEmbeddingRequest {
+    /// ID of the model to use
+    pub model: String,
+
+    /// The texts to embed, each of them yielding one embedding
+    pub input: Vec<String>,
+
+    /// The number of dimensions the resulting output embeddings should have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+
+    /// A unique identifier representing your end-user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateEmbeddingResponse {
+    /// The object type, which is always "list"
+    pub object: String,
+
+    /// The model used for the embeddings
+    pub model: String,
+
+    /// The list of embeddings, one for each input
+    pub data: Vec<EmbeddingObject>,
+
+    /// Usage statistics for the embeddings request
+    pub usage: EmbeddingUsage,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingObject {
+    /// The object type, which is always "embedding"
+    pub object: String,
+
+    /// The embedding vector
+    pub embedding: Vec<f32>,
+
+    /// The index of the input this embedding corresponds to
+    pub index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingUsage {
+    /// Number of tokens in the input.
+    pub prompt_tokens: u32,
+
+    /// Total number of tokens used.
+    pub total_tokens: u32,
+}
+
 /// A request for confidential computation that includes encrypted data and associated cryptographic parameters
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ConfidentialComputeRequest {